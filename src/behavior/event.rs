@@ -0,0 +1,227 @@
+//! Things that happen to the whole room for a while, like a lunch rush or a
+//! power outage. Each kind of event is described in `events.yml`.
+//!
+//! Events spread: dispatch can send one through the room as a wave, and any
+//! machine caught up in one passes a weaker version on to whoever it talks to.
+
+use crate::behavior::Personality;
+use lazy_static::*;
+use piston_window::Key;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    time::{Duration, SystemTime},
+};
+
+/// How much of an event's strength survives each step it spreads.
+pub const SPREAD_ATTENUATION: f64 = 0.6;
+/// How long a wave from dispatch takes to reach each next machine over.
+pub const WAVE_DELAY: Duration = Duration::from_secs(5);
+/// Events weaker than this stop spreading.
+const FAINTEST: f64 = 0.2;
+
+lazy_static! {
+    /// Every kind of event, by name.
+    pub static ref EVENTS: BTreeMap<String, EventKind> = {
+        let file = File::open("events.yml").unwrap();
+        serde_yaml::from_reader(file).unwrap()
+    };
+}
+
+/// How one kind of event plays out.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventKind {
+    /// Dispatch key that sets this event off.
+    pub key: Option<Key>,
+    /// How long it lasts, in seconds.
+    pub duration: u64,
+    /// Multiplies how hurried everyone is.
+    pub stress: f64,
+    #[serde(default)]
+    pub stock: StockEffect,
+    /// Added to personality traits while the event lasts, by trait name.
+    #[serde(default)]
+    pub modifiers: BTreeMap<String, f64>,
+}
+impl EventKind {
+    /// The name of the event a dispatch key sets off, if any.
+    pub fn for_key(key: Key) -> Option<&'static str> {
+        EVENTS
+            .iter()
+            .find(|(_, e)| e.key == Some(key))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// What an event does to the shelves as it starts.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StockEffect {
+    /// Fraction of everything in stock that goes bad.
+    #[serde(default)]
+    pub spoil: f64,
+    /// How many ingredients, picked at random, run out entirely.
+    #[serde(default)]
+    pub shortages: usize,
+    /// Whether a fresh delivery shows up.
+    #[serde(default)]
+    pub restock: bool,
+}
+
+/// Word of an event, passed along from dispatch or another machine.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventNotice {
+    pub name: String,
+    /// How hard it hits, from nothing at zero to the full event at one.
+    pub strength: f64,
+    /// How long it takes to reach us, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// How long ago it started where it first happened, in milliseconds.
+    /// Everyone caught up in it wraps up together, so echoes of an event
+    /// that's run its course don't set it off again.
+    #[serde(default)]
+    pub age_ms: u64,
+}
+
+impl EventNotice {
+    /// Word of an event for the machine `distance` steps away from where it
+    /// started, which reaches them later and weaker the further out they are.
+    pub fn at_distance(name: &str, distance: u32) -> Self {
+        Self {
+            name: name.to_owned(),
+            strength: SPREAD_ATTENUATION.powi(distance as i32),
+            delay_ms: WAVE_DELAY.as_millis() as u64 * distance as u64,
+            age_ms: 0,
+        }
+    }
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(self.age_ms)
+    }
+    /// Whether the event this is about would already be over by now.
+    pub fn is_stale(&self) -> bool {
+        EVENTS
+            .get(&self.name)
+            .is_none_or(|k| self.age() >= Duration::from_secs(k.duration))
+    }
+}
+
+/// An event underway on one machine.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Event {
+    pub name: String,
+    /// How hard it hit us, weaker the further it spread to get here.
+    #[serde(default = "full_strength")]
+    pub strength: f64,
+    started: SystemTime,
+    /// How far each trait actually moved, to put it back once we're done.
+    applied: BTreeMap<String, f64>,
+}
+impl Event {
+    /// Sets off the named event on this machine, nudging its personality in
+    /// proportion to `strength`. Only the machine an event actually happens
+    /// to, at full strength, loses stock over it; word of it just carries the
+    /// mood. It's already been going on for `age` wherever it started.
+    /// Nothing happens for an unknown event.
+    pub fn start(
+        name: &str,
+        strength: f64,
+        age: Duration,
+        personality: &mut Personality,
+    ) -> Option<Self> {
+        let kind = EVENTS.get(name)?;
+        let stock = &kind.stock;
+        if strength >= 1.0 {
+            if stock.restock {
+                personality.restock();
+            }
+            for count in personality.inventory.values_mut() {
+                *count *= 1.0 - stock.spoil;
+            }
+            let names: Vec<_> = personality.inventory.keys().cloned().collect();
+            for x in names.choose_multiple(&mut thread_rng(), stock.shortages) {
+                println!("Ran out of {}", x);
+                personality.inventory.insert(x.clone(), 0.0);
+            }
+        }
+
+        let mut applied = BTreeMap::new();
+        for (name, offset) in &kind.modifiers {
+            if let Some(value) = trait_mut(personality, name) {
+                let before = *value;
+                *value = (before + offset * strength).clamp(0.0, 1.0);
+                applied.insert(name.clone(), *value - before);
+            } else {
+                println!("No personality trait called {}", name);
+            }
+        }
+        Some(Self {
+            name: name.to_owned(),
+            strength,
+            started: SystemTime::now()
+                .checked_sub(age)
+                .unwrap_or_else(SystemTime::now),
+            applied,
+        })
+    }
+    /// Puts our personality back the way the event found it.
+    pub fn end(self, personality: &mut Personality) {
+        for (name, offset) in self.applied {
+            if let Some(value) = trait_mut(personality, &name) {
+                *value = (*value - offset).clamp(0.0, 1.0);
+            }
+        }
+    }
+    pub fn kind(&self) -> Option<&'static EventKind> {
+        EVENTS.get(&self.name)
+    }
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.kind().map_or(0, |k| k.duration))
+    }
+    pub fn stress(&self) -> f64 {
+        self.kind()
+            .map_or(1.0, |k| 1.0 + (k.stress - 1.0) * self.strength)
+    }
+    pub fn is_over(&self) -> bool {
+        self.started
+            .elapsed()
+            .map_or(true, |elapsed| elapsed > self.duration())
+    }
+    /// What we'd tell another machine about this event, if it's still
+    /// strong enough to spread.
+    pub fn passed_on(&self) -> Option<EventNotice> {
+        let strength = self.strength * SPREAD_ATTENUATION;
+        if strength < FAINTEST || self.is_over() {
+            return None;
+        }
+        Some(EventNotice {
+            name: self.name.clone(),
+            strength,
+            delay_ms: 0,
+            age_ms: self
+                .started
+                .elapsed()
+                .map_or(0, |age| age.as_millis() as u64),
+        })
+    }
+}
+
+fn full_strength() -> f64 {
+    1.0
+}
+
+/// The personality trait an event modifier refers to by name.
+fn trait_mut<'a>(personality: &'a mut Personality, name: &str) -> Option<&'a mut f64> {
+    match name {
+        "laziness" => Some(&mut personality.laziness),
+        "forgetfulness" => Some(&mut personality.forgetfulness),
+        "politeness" => Some(&mut personality.politeness),
+        "shyness" => Some(&mut personality.shyness),
+        "spite" => Some(&mut personality.spite),
+        "planned" => Some(&mut personality.planned),
+        "spontaneity" => Some(&mut personality.spontaneity),
+        "order_sensitivity" => Some(&mut personality.order_sensitivity),
+        _ => None,
+    }
+}
@@ -1,9 +1,11 @@
 mod allergy;
+mod event;
 pub mod ops;
 pub mod personality;
 
 // Re-export everything from behavior submodules.
 pub use allergy::*;
+pub use event::*;
 pub use ops::*;
 pub use personality::*;
 
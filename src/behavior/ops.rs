@@ -20,11 +20,15 @@
 //! it in other arbitrary ways.
 
 use crate::{
-    behavior::{Language, Personality},
+    behavior::{EventNotice, Language, Personality, TRADE_AMOUNT},
+    change::{
+        ordered, AddAfter, Adverbs, Change, Conjunction, Numbers, OBJECT_FIRST, POSTPOSITION,
+    },
     grammar::{AnnotatedPhrase, WordFunction},
-    sandwich::{Ingredient, Sandwich},
+    sandwich::{Ingredient, Portion, Preparation, Sandwich},
 };
 use async_std::{net::TcpStream, prelude::*};
+use itertools::Itertools;
 use piston_window::Button;
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
@@ -35,14 +39,65 @@ use serde_json;
 /// and the [Personality] passed to it.
 pub trait Operation: std::fmt::Debug {
     fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich;
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>>;
+    fn respond(&self, sandwich: &Sandwich, personality: &Personality)
+        -> Option<Box<dyn Operation>>;
     fn reverse(&self) -> Box<dyn Operation>;
-    fn question(&self) -> Box<dyn Operation>;
+    /// How to take this back once it's been done to `before`, for when
+    /// reversing it depends on what was there.
+    fn undo(&self, before: &Sandwich) -> Box<dyn Operation> {
+        self.reverse()
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation>;
+    /// Whether the sandwich already looks like this operation was done `times`
+    /// over, which is how we answer questions about it.
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool;
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase;
     fn is_persistent(&self) -> bool;
+    fn is_question(&self) -> bool {
+        false
+    }
+    /// Whether this is a plain "no", refusing or answering whatever came before.
+    fn is_negation(&self) -> bool {
+        false
+    }
+    /// Whether this is a plain "yes", agreeing to whatever came before.
+    fn is_affirmation(&self) -> bool {
+        false
+    }
+    /// Whether this moves stock between machines rather than touching the
+    /// sandwich, which always gets a plain yes or no back.
+    fn is_trade(&self) -> bool {
+        false
+    }
+    /// Whether this is money changing hands, as a bill or paying one.
+    fn is_payment(&self) -> bool {
+        false
+    }
     fn skills(&self) -> Language;
 }
 
+/// Answers a yes/no question about the given sandwich.
+fn answer(
+    question: &dyn Operation,
+    sandwich: &Sandwich,
+    personality: &Personality,
+) -> Box<dyn Operation> {
+    if question.holds(sandwich, personality, 1) {
+        Box::new(Affirm)
+    } else {
+        Box::new(Negate)
+    }
+}
+
+/// Counts the ingredients on a sandwich with the same name as the given one.
+fn count(sandwich: &Sandwich, ingredient: &Ingredient) -> u32 {
+    sandwich
+        .ingredients
+        .iter()
+        .filter(|x| x.name == ingredient.name)
+        .count() as u32
+}
+
 /// Add an ingredient to a sandwich, at the very end or relative to another ingredient.
 #[derive(Debug)]
 pub struct Add(pub Ingredient, pub Relative);
@@ -54,20 +109,31 @@ impl Operation for Add {
         }
 
         let mut ingr = sandwich.ingredients;
-        let idx = match &self.1 {
-            Relative::Before(other) => ingr.iter().position(|x| x.name == other.name),
-            Relative::After(other) => ingr
-                .iter()
-                .position(|x| x.name == other.name)
-                .map(|x| x + 1),
-            Relative::Top => Some(ingr.len()),
-        };
-        if let Some(idx) = idx {
-            ingr.insert(idx, self.0.clone());
-            personality.use_ingredient(&self.0);
+        if let Some(mut idx) = self.1.index(&ingr) {
+            let mut added = self.0.clone();
+            // Lazy servers grab whatever's next to it in the bin, or just toss
+            // it on top.
+            if personality.slacks_off() {
+                let mut rng = thread_rng();
+                let nearby = personality
+                    .dictionary
+                    .ingredients
+                    .neighbors(&self.0)
+                    .into_iter()
+                    .filter(|x| personality.has_ingredient(x))
+                    .choose(&mut rng)
+                    .cloned();
+                match nearby {
+                    Some(x) if rng.gen_bool(0.5) => {
+                        println!("Lazily grabbed {} instead of {}", x.name, self.0.name);
+                        added = x.with_portion(self.0.portion);
+                    }
+                    _ => idx = ingr.len(),
+                }
+            }
+            personality.use_ingredient(&added);
+            ingr.insert(idx, added);
         }
-        // Personality::upgrade_skill(&mut personality.spite);
-        // personality.spite += 0.05;
         Sandwich {
             ingredients: ingr,
             ..sandwich
@@ -77,48 +143,44 @@ impl Operation for Add {
         Box::new(Remove(self.0.clone()))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        // Encode prepositional phrase.
-        // TODO Use language weight for whether to actually use the adposition.
-        let mut prep = match &self.1 {
-            Relative::Before(other) => {
-                let p = lang.dictionary.annotated_word_for_def(WordFunction::Before);
-                let n = lang.dictionary.ingredients.to_annotated_word(&other);
-                vec![n, p]
-            }
-            Relative::After(other) => {
-                let p = lang.dictionary.annotated_word_for_def(WordFunction::After);
-                let n = lang.dictionary.ingredients.to_annotated_word(&other);
-                vec![n, p]
-            }
-            Relative::Top => vec![],
-        };
-
         // Get the word for our verb and ingredient.
         let want = lang.dictionary.annotated_word_for_def(WordFunction::Desire);
-        let ingr = lang.dictionary.ingredients.to_annotated_word(&self.0);
-        prep.push(ingr);
-        prep.push(want);
-        prep
+        let ingr = lang.dictionary.noun_phrase(&self.0);
+        let clause = ordered(lang, OBJECT_FIRST, ingr, vec![want]);
+        self.1.attach(lang, clause)
     }
     fn is_persistent(&self) -> bool {
         false
     }
     fn skills(&self) -> Language {
-        Language {
-            adposition: if self.1 == Relative::Top { 0 } else { 1 },
-            ..Default::default()
+        if self.1 == Relative::Top {
+            Default::default()
+        } else {
+            AddAfter.exercise()
         }
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         // Never add the requested ingredient if we don't have any more.
         if !personality.has_ingredient(&self.0) {
-            Some(Box::new(Remove(self.0.clone())))
+            return Some(Box::new(Remove(self.0.clone())));
+        }
+        // Say no if there's nothing to put it next to.
+        if self.1.anchors().iter().any(|x| count(sandwich, x) == 0) {
+            Some(Box::new(Negate))
         } else {
             None
         }
     }
-    fn question(&self) -> Box<dyn Operation> {
-        Box::new(CheckFor(self.0.clone()))
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        count(sandwich, &self.0) >= times
+            && (self.1 == Relative::Top || self.1.placed(&self.0, sandwich))
     }
 }
 
@@ -126,16 +188,179 @@ impl Operation for Add {
 pub enum Relative {
     Before(Ingredient),
     After(Ingredient),
+    /// Right between two layers that are next to each other, in either order.
+    Between(Ingredient, Ingredient),
     Top,
+    Bottom,
+    /// Right on top of the bottom layer, usually bread.
+    OnTopOfBase,
 }
 impl Relative {
-    pub fn from_def(def: WordFunction, ingredient: Ingredient) -> Self {
+    /// Number of ingredients an adposition with the given meaning takes.
+    pub fn arity(def: WordFunction) -> usize {
+        match def {
+            WordFunction::Before | WordFunction::After => 1,
+            WordFunction::Between => 2,
+            _ => 0,
+        }
+    }
+    /// Builds a position from an adposition and its ingredients, if there are
+    /// the right number of them.
+    pub fn from_def(def: WordFunction, anchors: Vec<Ingredient>) -> Option<Self> {
+        if anchors.len() != Self::arity(def) {
+            return None;
+        }
+        let mut anchors = anchors.into_iter();
+        let mut next = || anchors.next().unwrap();
         match def {
-            WordFunction::Before => Relative::Before(ingredient),
-            WordFunction::After => Relative::After(ingredient),
-            _ => Relative::Top,
+            WordFunction::Before => Some(Relative::Before(next())),
+            WordFunction::After => Some(Relative::After(next())),
+            WordFunction::Between => Some(Relative::Between(next(), next())),
+            WordFunction::Bottom => Some(Relative::Bottom),
+            WordFunction::OnTopOfBase => Some(Relative::OnTopOfBase),
+            _ => None,
+        }
+    }
+    /// The ingredients this position is relative to.
+    pub fn anchors(&self) -> Vec<&Ingredient> {
+        match self {
+            Relative::Before(other) | Relative::After(other) => vec![other],
+            Relative::Between(a, b) => vec![a, b],
+            Relative::Top | Relative::Bottom | Relative::OnTopOfBase => Vec::new(),
         }
     }
+    /// Where a new layer would go in the given stack, if its anchors are there.
+    pub fn index(&self, ingredients: &[Ingredient]) -> Option<usize> {
+        match self {
+            Relative::Before(other) => ingredients.iter().position(|x| x.name == other.name),
+            Relative::After(other) => ingredients
+                .iter()
+                .position(|x| x.name == other.name)
+                .map(|x| x + 1),
+            Relative::Between(a, b) => ingredients
+                .iter()
+                .tuple_windows()
+                .position(|(x, y)| {
+                    (x.name == a.name && y.name == b.name) || (x.name == b.name && y.name == a.name)
+                })
+                .map(|x| x + 1),
+            Relative::Top => Some(ingredients.len()),
+            Relative::Bottom => Some(0),
+            Relative::OnTopOfBase => Some(ingredients.len().min(1)),
+        }
+    }
+    /// Whether the given ingredient sits at this position on the sandwich.
+    pub fn placed(&self, ingredient: &Ingredient, sandwich: &Sandwich) -> bool {
+        let ingredients = &sandwich.ingredients;
+        let is = |x: Option<&Ingredient>| x.map_or(false, |x| x.name == ingredient.name);
+        match self {
+            Relative::Before(other) => ingredients
+                .iter()
+                .tuple_windows()
+                .any(|(a, b)| a.name == ingredient.name && b.name == other.name),
+            Relative::After(other) => ingredients
+                .iter()
+                .tuple_windows()
+                .any(|(a, b)| a.name == other.name && b.name == ingredient.name),
+            Relative::Between(a, b) => ingredients.iter().tuple_windows().any(|(x, y, z)| {
+                y.name == ingredient.name
+                    && ((x.name == a.name && z.name == b.name)
+                        || (x.name == b.name && z.name == a.name))
+            }),
+            Relative::Top => is(ingredients.last()),
+            Relative::Bottom => is(ingredients.first()),
+            Relative::OnTopOfBase => is(ingredients.get(1)),
+        }
+    }
+    /// The opposite side of the same anchor.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Relative::Before(other) => Relative::After(other.clone()),
+            Relative::After(other) => Relative::Before(other.clone()),
+            Relative::Between(a, b) => Relative::Between(a.clone(), b.clone()),
+            Relative::Top => Relative::Bottom,
+            Relative::Bottom | Relative::OnTopOfBase => Relative::Top,
+        }
+    }
+    /// Encodes the adpositional phrase for this position alongside the clause it
+    /// modifies, or just the clause for the top.
+    pub fn attach(&self, lang: &Personality, clause: AnnotatedPhrase) -> AnnotatedPhrase {
+        // TODO Use language weight for whether to actually use the adposition.
+        let def = match self {
+            Relative::Before(_) => WordFunction::Before,
+            Relative::After(_) => WordFunction::After,
+            Relative::Between(_, _) => WordFunction::Between,
+            Relative::Bottom => WordFunction::Bottom,
+            Relative::OnTopOfBase => WordFunction::OnTopOfBase,
+            Relative::Top => return clause,
+        };
+        let p = lang.dictionary.annotated_word_for_def(def);
+        let n = self
+            .anchors()
+            .into_iter()
+            .flat_map(|x| lang.dictionary.noun_phrase(x))
+            .collect();
+        let prep = ordered(lang, POSTPOSITION, n, vec![p]);
+        AddAfter.attach(lang, clause, prep)
+    }
+}
+
+/// Move an ingredient that's already on the sandwich to a new position.
+#[derive(Debug)]
+pub struct Move(pub Ingredient, pub Relative);
+impl Operation for Move {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        let mut ingr = sandwich.ingredients;
+        if let Some(from) = ingr.iter().position(|x| x.name == self.0.name) {
+            let layer = ingr.remove(from);
+            // Put it back where it was if there's nowhere to move it to.
+            let to = self.1.index(&ingr).unwrap_or(from);
+            ingr.insert(to, layer);
+        }
+        Sandwich {
+            ingredients: ingr,
+            ..sandwich
+        }
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        // We don't know exactly where it was, so just the other side will do.
+        Box::new(Move(self.0.clone(), self.1.opposite()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Move);
+        let ingr = lang.dictionary.noun_phrase(&self.0);
+        let clause = ordered(lang, OBJECT_FIRST, ingr, vec![verb]);
+        self.1.attach(lang, clause)
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn skills(&self) -> Language {
+        if self.1 == Relative::Top {
+            Default::default()
+        } else {
+            AddAfter.exercise()
+        }
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        // Say no if there's nothing to move, or nothing to put it next to.
+        let anchor_missing = self.1.anchors().iter().any(|x| count(sandwich, x) == 0);
+        if count(sandwich, &self.0) == 0 || anchor_missing {
+            Some(Box::new(Negate))
+        } else {
+            None
+        }
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.1.placed(&self.0, sandwich)
+    }
 }
 
 /// Remove the given ingredient from a sandwich.
@@ -143,12 +368,15 @@ impl Relative {
 pub struct Remove(pub Ingredient);
 impl Operation for Remove {
     fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        // Lazy servers might not bother picking it off.
+        if personality.slacks_off() {
+            println!("Lazily left {} on", self.0.name);
+            return sandwich;
+        }
         let mut ingredients = sandwich.ingredients;
         if let Some(idx) = ingredients.iter().position(|x| x.name == self.0.name) {
             ingredients.remove(idx);
         }
-        // Ingredient removal raises spite!
-        // Personality::upgrade_skill(&mut personality.spite);
         Sandwich {
             ingredients,
             ..sandwich
@@ -158,27 +386,29 @@ impl Operation for Remove {
         Box::new(Add(self.0.clone(), Relative::Top))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        let mut phrase = self.reverse().encode(lang);
         let neg = lang
             .dictionary
             .annotated_word_for_def(WordFunction::Negation);
-        phrase.insert(0, neg);
-        phrase
+        Adverbs.attach(lang, self.reverse().encode(lang), vec![neg])
     }
     fn is_persistent(&self) -> bool {
         false
     }
     fn skills(&self) -> Language {
-        Language {
-            adverbs: 1,
-            ..Default::default()
-        }
+        Adverbs.exercise()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        !sandwich.ingredients.iter().any(|x| self.0.includes(x))
     }
 }
 
@@ -200,24 +430,433 @@ impl Operation for RemoveAll {
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
         let all = lang.dictionary.annotated_word_for_def(WordFunction::Ever);
-        let mut inner = Remove(self.0.clone()).encode(lang);
-        inner.insert(0, all);
-        inner
+        Adverbs.attach(lang, Remove(self.0.clone()).encode(lang), vec![all])
     }
     fn is_persistent(&self) -> bool {
         false
     }
     fn skills(&self) -> Language {
-        Language {
-            adverbs: 1,
-            ..Default::default()
+        Adverbs.exercise()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        None
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        !sandwich.ingredients.iter().any(|x| self.0.includes(x))
+    }
+}
+
+/// Trade the places of two ingredients already on the sandwich, naming the
+/// lower one first.
+#[derive(Debug)]
+pub struct Swap(pub Ingredient, pub Ingredient);
+impl Operation for Swap {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        let mut ingredients = sandwich.ingredients;
+        let a = ingredients.iter().position(|x| x.name == self.0.name);
+        let b = ingredients.iter().position(|x| x.name == self.1.name);
+        if let (Some(a), Some(b)) = (a, b) {
+            ingredients.swap(a, b);
+        }
+        Sandwich {
+            ingredients,
+            ..sandwich
+        }
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        // Swapping them back, the other one sits lower now.
+        Box::new(Swap(self.1.clone(), self.0.clone()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Swap);
+        // "bacon and cheese swap"
+        let and = lang.dictionary.annotated_word_for_def(WordFunction::And);
+        let mut second = vec![and];
+        second.extend(lang.dictionary.noun_phrase(&self.1));
+        let objects = Conjunction.attach(lang, lang.dictionary.noun_phrase(&self.0), second);
+        ordered(lang, OBJECT_FIRST, objects, vec![verb])
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn skills(&self) -> Language {
+        Conjunction.exercise()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        // Say no if either one isn't there to move.
+        if count(sandwich, &self.0) == 0 || count(sandwich, &self.1) == 0 {
+            Some(Box::new(Negate))
+        } else {
+            None
+        }
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        // Once swapped, the one we named second sits lower.
+        let a = sandwich
+            .ingredients
+            .iter()
+            .position(|x| x.name == self.0.name);
+        let b = sandwich
+            .ingredients
+            .iter()
+            .position(|x| x.name == self.1.name);
+        matches!((a, b), (Some(a), Some(b)) if b < a)
+    }
+}
+
+/// Put a new ingredient where an old one was, taking the old one off.
+#[derive(Debug)]
+pub struct Replace(pub Ingredient, pub Ingredient);
+impl Operation for Replace {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        if !personality.has_ingredient(&self.1) {
+            return sandwich;
+        }
+        let mut ingredients = sandwich.ingredients;
+        if let Some(idx) = ingredients.iter().position(|x| x.name == self.0.name) {
+            ingredients[idx] = self.1.clone();
+            personality.use_ingredient(&self.1);
+        }
+        Sandwich {
+            ingredients,
+            ..sandwich
+        }
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Replace(self.1.clone(), self.0.clone()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang
+            .dictionary
+            .annotated_word_for_def(WordFunction::Replace);
+        // "bacon cheese with replace", the new one marked like an adposition.
+        let with = lang.dictionary.annotated_word_for_def(WordFunction::With);
+        let mut objects = lang.dictionary.noun_phrase(&self.0);
+        objects.extend(ordered(
+            lang,
+            POSTPOSITION,
+            lang.dictionary.noun_phrase(&self.1),
+            vec![with],
+        ));
+        ordered(lang, OBJECT_FIRST, objects, vec![verb])
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn skills(&self) -> Language {
+        AddAfter.exercise()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        // Never swap in the new ingredient if we don't have any more.
+        if !personality.has_ingredient(&self.1) {
+            Some(Box::new(Remove(self.1.clone())))
+        } else if count(sandwich, &self.1) == 0 {
+            Some(Box::new(Negate))
+        } else {
+            None
         }
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        count(sandwich, &self.0) == 0 && count(sandwich, &self.1) >= times
+    }
+}
+
+/// Changes how much of an ingredient is in its layer, like "make it extra cheese".
+/// The ingredient carries the portion we want.
+#[derive(Debug, Clone)]
+pub struct SetPortion(pub Ingredient);
+impl Operation for SetPortion {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        let mut ingredients = sandwich.ingredients;
+        if let Some(layer) = ingredients.iter_mut().find(|x| x.name == self.0.name) {
+            // Adding more takes from stock, but we don't put anything back.
+            let more = self.0.portion.amount() - layer.portion.amount();
+            if more <= 0.0 || personality.has_ingredient(&self.0) {
+                personality.use_amount(&self.0, more.max(0.0));
+                layer.portion = self.0.portion;
+            }
+        }
+        Sandwich {
+            ingredients,
+            ..sandwich
+        }
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(SetPortion(self.0.with_portion(Portion::Normal)))
+    }
+    fn undo(&self, before: &Sandwich) -> Box<dyn Operation> {
+        // Put back however much there was.
+        match before.ingredients.iter().find(|x| x.name == self.0.name) {
+            Some(layer) => Box::new(SetPortion(self.0.with_portion(layer.portion))),
+            None => self.reverse(),
+        }
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Make);
+        ordered(
+            lang,
+            OBJECT_FIRST,
+            lang.dictionary.noun_phrase(&self.0),
+            vec![verb],
+        )
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn skills(&self) -> Language {
+        Default::default()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        if count(sandwich, &self.0) == 0 {
+            Some(Box::new(Negate))
+        } else {
+            None
+        }
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        sandwich
+            .ingredients
+            .iter()
+            .filter(|x| **x == self.0 && x.portion == self.0.portion)
+            .count() as u32
+            >= times
+    }
+}
+
+/// Cooks a layer some way, like "toast the bread".
+/// The ingredient carries the preparation we want.
+#[derive(Debug, Clone)]
+pub struct Prepare(pub Ingredient);
+impl Operation for Prepare {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        let mut ingredients = sandwich.ingredients;
+        if let Some(layer) = ingredients.iter_mut().find(|x| x.name == self.0.name) {
+            layer.preparation = self.0.preparation;
+        }
+        Sandwich {
+            ingredients,
+            ..sandwich
+        }
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Prepare(self.0.with_preparation(Preparation::Plain)))
+    }
+    fn undo(&self, before: &Sandwich) -> Box<dyn Operation> {
+        // Cook it back the way it was, as far as that goes.
+        match before.ingredients.iter().find(|x| x.name == self.0.name) {
+            Some(layer) => Box::new(Prepare(self.0.with_preparation(layer.preparation))),
+            None => self.reverse(),
+        }
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang
+            .dictionary
+            .annotated_word_for_def(WordFunction::Prepare);
+        ordered(
+            lang,
+            OBJECT_FIRST,
+            lang.dictionary.noun_phrase(&self.0),
+            vec![verb],
+        )
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn skills(&self) -> Language {
+        Default::default()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        if count(sandwich, &self.0) == 0 {
+            Some(Box::new(Negate))
+        } else {
+            None
+        }
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        sandwich
+            .ingredients
+            .iter()
+            .filter(|x| **x == self.0 && x.preparation == self.0.preparation)
+            .count() as u32
+            >= times
+    }
+}
+
+/// Hands a trade's worth of an ingredient over to whoever hears it. The
+/// speaker takes it out of their own stock with [Operation::reverse].
+#[derive(Debug)]
+pub struct Give(pub Ingredient);
+impl Operation for Give {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        personality.receive(&self.0, TRADE_AMOUNT);
+        sandwich
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Take(self.0.clone()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Give);
+        ordered(
+            lang,
+            OBJECT_FIRST,
+            lang.dictionary.noun_phrase(&self.0),
+            vec![verb],
+        )
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn is_trade(&self) -> bool {
+        true
+    }
+    fn skills(&self) -> Language {
+        Default::default()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        None
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        // We'd only take more of something we're not already flush with.
+        !personality.can_spare(&self.0)
+    }
+}
+
+/// Takes a trade's worth of an ingredient out of the hearer's stock, for the
+/// speaker to keep. Nobody hands over what they can't spare.
+#[derive(Debug)]
+pub struct Take(pub Ingredient);
+impl Operation for Take {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        if personality.can_spare(&self.0) {
+            personality.use_amount(&self.0, TRADE_AMOUNT);
+        }
+        sandwich
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Give(self.0.clone()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Take);
+        ordered(
+            lang,
+            OBJECT_FIRST,
+            lang.dictionary.noun_phrase(&self.0),
+            vec![verb],
+        )
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn is_trade(&self) -> bool {
+        true
+    }
+    fn skills(&self) -> Language {
+        Default::default()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        personality.can_spare(&self.0)
+    }
+}
+
+/// Money changing hands across the counter. Whoever's serving takes it,
+/// while a customer hearing it has been handed the bill and pays what they can.
+#[derive(Debug)]
+pub struct Pay(pub u32);
+impl Operation for Pay {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        if personality.serving {
+            personality.wallet += self.0;
+        } else {
+            personality.wallet = personality.wallet.saturating_sub(self.0);
+        }
+        sandwich
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        // Money once handed over stays handed over.
+        Box::new(Pay(self.0))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let verb = lang.dictionary.annotated_word_for_def(WordFunction::Pay);
+        ordered(
+            lang,
+            OBJECT_FIRST,
+            lang.dictionary.numeral(self.0),
+            vec![verb],
+        )
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn is_payment(&self) -> bool {
+        true
+    }
+    fn skills(&self) -> Language {
+        Default::default()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        None
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        personality.wallet >= self.0
     }
 }
 
@@ -245,11 +884,18 @@ impl Operation for Finish {
     fn skills(&self) -> Language {
         Default::default()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        sandwich.complete
     }
 }
 
@@ -268,26 +914,36 @@ impl Operation for Repeat {
         Box::new(Self(self.0, self.1.reverse()))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        let mut phrase = self.1.encode(lang);
-        let num = lang.dictionary.annotated_word_for_num(self.0);
-        phrase.insert(0, num);
-        phrase
+        let num = lang.dictionary.numeral(self.0);
+        Numbers.attach(lang, self.1.encode(lang), num)
     }
     fn is_persistent(&self) -> bool {
         false
     }
+    fn is_question(&self) -> bool {
+        self.1.is_question()
+    }
     fn skills(&self) -> Language {
-        self.1.skills()
-            + Language {
-                numbers: 1,
-                ..Default::default()
-            }
+        self.1.skills() + Numbers.exercise()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
-        None
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        if self.is_question() {
+            Some(answer(self, sandwich, personality))
+        } else {
+            self.1.respond(sandwich, personality)
+        }
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        // Numbers go outside of adverbs, so ask "two of (is there egg?)" to
+        // mean "are there two eggs?"
+        Box::new(Repeat(self.0, self.1.question()))
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.1.holds(sandwich, personality, self.0 * times)
     }
 }
 
@@ -305,29 +961,39 @@ impl Operation for Compound {
         Box::new(Compound(self.0.reverse(), self.1.reverse()))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        let mut phrase = self.0.encode(lang);
-        let conj = lang.dictionary.annotated_word_for_def(WordFunction::And);
+        let mut second = vec![lang.dictionary.annotated_word_for_def(WordFunction::And)];
+        second.append(&mut self.1.encode(lang));
         // Conjunction goes between two sub-phrases.
-        phrase.push(conj);
-        phrase.append(&mut self.1.encode(lang));
-        phrase
+        Conjunction.attach(lang, self.0.encode(lang), second)
     }
     fn is_persistent(&self) -> bool {
         false
     }
+    fn is_question(&self) -> bool {
+        self.0.is_question() && self.1.is_question()
+    }
     fn skills(&self) -> Language {
-        self.0.skills()
-            + self.1.skills()
-            + Language {
-                conjunction: 1,
-                ..Default::default()
-            }
+        self.0.skills() + self.1.skills() + Conjunction.exercise()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
-        None
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        if self.is_question() {
+            // Answer both questions at once, "is there A and B?"
+            Some(answer(self, sandwich, personality))
+        } else {
+            self.0
+                .respond(sandwich, personality)
+                .or_else(|| self.1.respond(sandwich, personality))
+        }
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Compound(self.0.question(), self.1.question()))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.0.holds(sandwich, personality, times) && self.1.holds(sandwich, personality, times)
     }
 }
 
@@ -344,8 +1010,8 @@ impl Operation for Ensure {
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
         let verb = lang.dictionary.annotated_word_for_def(WordFunction::Have);
-        let ingr = lang.dictionary.ingredients.to_annotated_word(&self.0);
-        vec![ingr, verb]
+        let ingr = lang.dictionary.noun_phrase(&self.0);
+        ordered(lang, OBJECT_FIRST, ingr, vec![verb])
     }
     fn is_persistent(&self) -> bool {
         false
@@ -353,11 +1019,18 @@ impl Operation for Ensure {
     fn skills(&self) -> Language {
         Default::default()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        Box::new(CheckFor(self.0.clone()))
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(CheckFor(self.0))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        count(sandwich, &self.0) >= times
     }
 }
 
@@ -373,22 +1046,75 @@ impl Operation for Persist {
         Box::new(Persist(self.0.reverse()))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        let mut p = self.0.encode(lang);
         let ever = lang.dictionary.annotated_word_for_def(WordFunction::Ever);
-        p.insert(0, ever);
-        p
+        Adverbs.attach(lang, self.0.encode(lang), vec![ever])
     }
     fn is_persistent(&self) -> bool {
         true
     }
+    fn is_question(&self) -> bool {
+        self.0.is_question()
+    }
     fn skills(&self) -> Language {
         self.0.skills()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
-        None
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        self.0.respond(sandwich, personality)
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.0.holds(sandwich, personality, times)
+    }
+}
+
+/// Asks for something nicely, with a "please" tacked on the end.
+/// Listeners hear the particle anywhere in a sentence, so it never reaches
+/// the parser as an operation of its own.
+#[derive(Debug)]
+pub struct Polite(pub Box<dyn Operation>);
+impl Operation for Polite {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        self.0.apply(sandwich, personality)
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Polite(self.0.reverse()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let please = lang.dictionary.annotated_word_for_def(WordFunction::Polite);
+        let mut phrase = self.0.encode(lang);
+        phrase.push(please);
+        phrase
+    }
+    fn is_persistent(&self) -> bool {
+        self.0.is_persistent()
+    }
+    fn is_question(&self) -> bool {
+        self.0.is_question()
+    }
+    fn is_negation(&self) -> bool {
+        self.0.is_negation()
+    }
+    fn skills(&self) -> Language {
+        self.0.skills()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        self.0.respond(sandwich, personality)
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Polite(self.0.question()))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.0.holds(sandwich, personality, times)
     }
 }
 
@@ -413,7 +1139,7 @@ impl Operation for Affirm {
         sandwich
     }
     fn reverse(&self) -> Box<dyn Operation> {
-        todo!("Add pure negation operation")
+        Box::new(Negate)
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
         let w = lang
@@ -427,20 +1153,29 @@ impl Operation for Affirm {
     fn skills(&self) -> Language {
         Default::default()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        true
+    }
+    fn is_affirmation(&self) -> bool {
+        true
     }
 }
 
-/// Just a dummy to act as a foil for [Affirm].
+/// A plain "no" as a foil for [Affirm], which listeners hear as [NegateLast].
 #[derive(Debug)]
 pub struct Negate;
 impl Operation for Negate {
     fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
-        // TODO Maybe do something here?
         sandwich
     }
     fn reverse(&self) -> Box<dyn Operation> {
@@ -458,44 +1193,69 @@ impl Operation for Negate {
     fn skills(&self) -> Language {
         Default::default()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         None
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        false
+    }
+    fn is_negation(&self) -> bool {
+        true
     }
 }
 
 /// Negates the last operation we requested, operates practically as a negative
-/// response to a question.
+/// response to a question. Whoever hears it takes back their last move, kept
+/// in [Personality::undo].
 #[derive(Debug)]
 pub struct NegateLast;
 impl Operation for NegateLast {
     fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
-        todo!()
+        if let Some(undo) = personality.undo.take() {
+            undo.apply(sandwich, personality)
+        } else {
+            sandwich
+        }
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
-        todo!()
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        None
     }
     fn reverse(&self) -> Box<dyn Operation> {
-        todo!()
+        Box::new(Affirm)
     }
-    fn question(&self) -> Box<dyn Operation> {
-        todo!()
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        Box::new(Question(self))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        false
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
-        todo!()
+        Negate.encode(lang)
     }
     fn is_persistent(&self) -> bool {
-        todo!()
+        false
+    }
+    fn is_negation(&self) -> bool {
+        true
     }
     fn skills(&self) -> Language {
-        todo!()
+        Default::default()
     }
 }
 
-/// TODO Consider turning this into a generic question wrapper. Adds the
-/// modifier, makes no application, but must send a response. Think on it.
+/// Asks whether we have an ingredient in stock at all, rather than whether
+/// it's on the sandwich.
 #[derive(Debug)]
 struct CheckFor(pub Ingredient);
 impl Operation for CheckFor {
@@ -503,23 +1263,31 @@ impl Operation for CheckFor {
         sandwich
     }
     fn reverse(&self) -> Box<dyn Operation> {
-        todo!()
+        // Being told "no" means going without it.
+        Box::new(Remove(self.0.clone()))
     }
     fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
         let q = lang
             .dictionary
             .annotated_word_for_def(WordFunction::Question);
         let verb = lang.dictionary.annotated_word_for_def(WordFunction::Have);
-        let n = lang.dictionary.ingredients.to_annotated_word(&self.0);
-        vec![q, n, verb]
+        let n = lang.dictionary.noun_phrase(&self.0);
+        Adverbs.attach(lang, ordered(lang, OBJECT_FIRST, n, vec![verb]), vec![q])
     }
     fn is_persistent(&self) -> bool {
         false
     }
+    fn is_question(&self) -> bool {
+        true
+    }
     fn skills(&self) -> Language {
         Default::default()
     }
-    fn respond(&self, personality: &Personality) -> Option<Box<dyn Operation>> {
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
         // If we have the asked for ingredient, respond positively.
         if !personality.has_ingredient(&self.0) {
             Some(Box::new(Remove(self.0.clone())))
@@ -527,9 +1295,54 @@ impl Operation for CheckFor {
             Some(Box::new(Ensure(self.0.clone())))
         }
     }
-    fn question(&self) -> Box<dyn Operation> {
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
         // A questioned question becomes a statement.
-        Box::new(Add(self.0.clone(), Relative::Top))
+        Box::new(Add(self.0, Relative::Top))
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        personality.has_ingredient(&self.0)
+    }
+}
+
+/// Asks whether the sandwich already looks like the inner operation was done,
+/// without changing it. "Is there no cheese?" "Did you add two eggs?"
+#[derive(Debug)]
+pub struct Question(pub Box<dyn Operation>);
+impl Operation for Question {
+    fn apply(&self, sandwich: Sandwich, personality: &mut Personality) -> Sandwich {
+        sandwich
+    }
+    fn reverse(&self) -> Box<dyn Operation> {
+        Box::new(Question(self.0.reverse()))
+    }
+    fn encode(&self, lang: &Personality) -> AnnotatedPhrase {
+        let q = lang
+            .dictionary
+            .annotated_word_for_def(WordFunction::Question);
+        Adverbs.attach(lang, self.0.encode(lang), vec![q])
+    }
+    fn is_persistent(&self) -> bool {
+        false
+    }
+    fn is_question(&self) -> bool {
+        true
+    }
+    fn skills(&self) -> Language {
+        self.0.skills() + Adverbs.exercise()
+    }
+    fn respond(
+        &self,
+        sandwich: &Sandwich,
+        personality: &Personality,
+    ) -> Option<Box<dyn Operation>> {
+        Some(answer(self, sandwich, personality))
+    }
+    fn question(self: Box<Self>) -> Box<dyn Operation> {
+        // A questioned question becomes a statement.
+        self.0
+    }
+    fn holds(&self, sandwich: &Sandwich, personality: &Personality, times: u32) -> bool {
+        self.0.holds(sandwich, personality, times)
     }
 }
 
@@ -566,15 +1379,21 @@ pub struct Order {
     history: Vec<Box<dyn Operation>>,
     pub last_result: Option<Sandwich>,
     pub persistent_ops: Vec<Box<dyn Operation>>,
+    /// Whether the last thing we heard back was a plain "no".
+    pub heard_no: bool,
+    /// Whether we care how this sandwich is stacked, or just what's on it.
+    pub picky: bool,
 }
 impl Order {
     pub fn new(lang: &Personality) -> Self {
         Self {
             history: Vec::new(),
             // TODO Pick a sandwich based on our personality.
-            desired: lang.gen_sandwich(7),
+            desired: lang.within_budget(lang.gen_sandwich(7)),
             persistent_ops: Vec::new(),
             last_result: None,
+            heard_no: false,
+            picky: thread_rng().gen_bool(lang.order_sensitivity),
         }
     }
 
@@ -593,8 +1412,19 @@ impl Order {
                 let imagined_result = op.apply(last_res.clone(), personality);
                 // Only successful if there was some intended difference *and*
                 // it was the correct difference.
-                return last_res.ingredients != imagined_result.ingredients
-                    && imagined_result.ingredients == result.ingredients;
+                return !last_res.same_layers(&imagined_result)
+                    && imagined_result.same_layers(result);
+            }
+        }
+        false
+    }
+
+    /// Whether the sandwich changed since our last request, but not the way we asked.
+    pub fn last_op_mistaken(&self, personality: &mut Personality, result: &Sandwich) -> bool {
+        if let Some(op) = self.last_op() {
+            if let Some(last_res) = self.last_result.as_ref() {
+                let imagined_result = op.apply(last_res.clone(), personality);
+                return !last_res.same_layers(result) && !imagined_result.same_layers(result);
             }
         }
         false
@@ -605,14 +1435,22 @@ impl Order {
             if let Some(last_res) = self.last_result.as_ref() {
                 let imagined_result = op.apply(last_res.clone(), personality);
                 // Make sure it's a question (doesn't affect our sandwich).
-                let is_question = last_res.ingredients == imagined_result.ingredients;
+                let is_question = last_res.same_layers(&imagined_result);
                 // Negative result if our sandwich had to change because of the answer.
-                return is_question && imagined_result.ingredients != result.ingredients;
+                return is_question && !imagined_result.same_layers(result);
             }
         }
         false
     }
 
+    /// Decide we want another layer after all, and ask for it on top of what
+    /// we have so far.
+    fn want_more(&mut self, ingredient: Ingredient, result: &Sandwich) -> Box<dyn Operation> {
+        let idx = result.ingredients.len().min(self.desired.ingredients.len());
+        self.desired.ingredients.insert(idx, ingredient.clone());
+        Box::new(Add(ingredient, Relative::Top))
+    }
+
     /// Based on the current conversation state and resulting sandwich, choose
     /// an operation to ask our conversation partner to apply to said sandwich.
     pub fn pick_op(
@@ -624,14 +1462,71 @@ impl Order {
 
         self.last_result = Some(result.clone());
 
-        // If the result has all the ingredients we want, then we're finished.
-        let has_all = self
-            .desired
-            .ingredients
-            .iter()
-            .all(|x| result.ingredients.contains(x));
+        // If the result has exactly the ingredients we want, then we're finished.
+        let has_all = result.same_ingredients(&self.desired);
         if has_all {
-            return None;
+            // Picky customers care about the order of the stack, not just
+            // what's on it, so move the first layer that's out of place.
+            let out_of_place = result
+                .ingredients
+                .iter()
+                .zip(&self.desired.ingredients)
+                .position(|(x, y)| x != y);
+            if let Some(idx) = out_of_place.filter(|_| self.picky) {
+                let rel = if idx == 0 {
+                    Relative::Bottom
+                } else {
+                    Relative::After(self.desired.ingredients[idx - 1].clone())
+                };
+                return Some(Box::new(Move(self.desired.ingredients[idx].clone(), rel)));
+            }
+            // Ask for more or less of anything that wasn't portioned or
+            // cooked how we like.
+            let wrong_style = self.desired.ingredients.iter().find(|x| {
+                !result
+                    .ingredients
+                    .iter()
+                    .any(|y| y == *x && y.same_style(x))
+            });
+            if let Some(x) = wrong_style {
+                if !rng.gen_bool((personality.shyness / personality.stress()).min(0.99)) {
+                    let portioned = result
+                        .ingredients
+                        .iter()
+                        .any(|y| y == x && y.portion == x.portion);
+                    return Some(if portioned {
+                        Box::new(Prepare(x.clone()))
+                    } else {
+                        Box::new(SetPortion(x.clone()))
+                    });
+                }
+            }
+            // If they told us an allergen is on there after all, ask for it off.
+            if std::mem::take(&mut self.heard_no) {
+                let allergen = result.ingredients.iter().find(|x| {
+                    personality
+                        .allergies
+                        .iter()
+                        .any(|a| a.ingredient.includes(x))
+                });
+                if let Some(allergen) = allergen {
+                    return Some(Box::new(Remove(allergen.clone())));
+                }
+            }
+            // Careful machines double check that none of their allergens made
+            // it onto the sandwich before calling it done, unless they're in a
+            // hurry.
+            let already_asked = self.last_op().map_or(false, |op| op.is_question());
+            let allergen = personality.allergies.iter().find(|a| {
+                rng.gen_bool(a.severity)
+                    && rng.gen_bool((personality.planned / personality.stress()).min(0.99))
+            });
+            return match allergen {
+                Some(a) if !already_asked && rng.gen_bool(Adverbs.fluency(personality)) => {
+                    Some(Box::new(Question(Box::new(Remove(a.ingredient.clone())))))
+                }
+                _ => None,
+            };
         }
 
         // The basic behavior: pick the next ingredient on the sandwich.
@@ -666,18 +1561,26 @@ impl Order {
             next_idx += 1;
         }
 
-        // TODO When considering a removal, maybe try to do a swap instead.
-
         // There could be extra ingredients that we didn't ask for.
         let extra = result
             .ingredients
             .iter()
-            .find(|x| !self.desired.ingredients.contains(x));
-        if let Some(extra) = extra {
-            if !rng.gen_bool(personality.shyness / personality.stress()) {
+            .enumerate()
+            .find(|(_, x)| !self.desired.ingredients.contains(x));
+        if let Some((idx, extra)) = extra {
+            if !rng.gen_bool((personality.shyness / personality.stress()).min(0.99)) {
+                // If it's sitting where something we want belongs, swap that in instead.
+                let wanted = self
+                    .desired
+                    .ingredients
+                    .get(idx)
+                    .filter(|x| !result.ingredients.contains(x));
+                if let Some(wanted) = wanted {
+                    return Some(Box::new(Replace(extra.clone(), wanted.clone())));
+                }
                 // FIXME Not *exactly* what we want, but close.
                 let remover = Box::new(Remove(extra.clone()));
-                return Some(if rng.gen_bool(personality.adverbs) {
+                return Some(if rng.gen_bool(Adverbs.fluency(personality)) {
                     Box::new(Persist(remover))
                 } else {
                     remover
@@ -685,32 +1588,59 @@ impl Order {
             }
         }
 
-        // There's a mistake if any preceding ingredients aren't in the result sandwich.
-        // NOTE disregarding order for the moment.
-        let mistake = self
-            .desired
+        // Two of the ingredients we want may have traded places.
+        let misplaced = result
             .ingredients
+            .iter()
+            .zip(&self.desired.ingredients)
+            .enumerate()
+            .filter(|(_, (x, y))| x != y)
+            .map(|(i, _)| i)
+            .collect_vec();
+        if let [a, b] = misplaced[..] {
+            let (x, y) = (&result.ingredients[a], &result.ingredients[b]);
+            if self.picky
+                && x == &self.desired.ingredients[b]
+                && y == &self.desired.ingredients[a]
+                && !rng.gen_bool((personality.shyness / personality.stress()).min(0.99))
+            {
+                return Some(Box::new(Swap(x.clone(), y.clone())));
+            }
+        }
+
+        // There's a mistake if any preceding ingredients aren't in the result
+        // sandwich as many times as we've asked for them so far.
+        let desired = &self.desired.ingredients;
+        let mistake = desired
             .iter()
             .take(next_idx)
-            .position(|x| !result.ingredients.contains(x));
+            .enumerate()
+            .position(|(i, x)| {
+                desired[..=i].iter().filter(|y| *y == x).count() as u32 > count(result, x)
+            });
         // If we aren't shy, try to correct a mistake!
         if mistake.is_some()
-            && !rng.gen_bool(personality.shyness / personality.stress())
-            && rng.gen_bool((personality.adposition * 1.5).min(0.99))
+            && !rng.gen_bool((personality.shyness / personality.stress()).min(0.99))
+            && rng.gen_bool((AddAfter.fluency(personality) * 1.5).min(0.99))
         {
             let idx = mistake.unwrap();
-            // Pick a preposition to position the missing ingredient where we'd like it.
-            // TODO If this machine doesn't care about ordering, then just ask to add it to the end.
-            // TODO if idx is zero or if this personality has a preference for Before.
+            // Pick a preposition to position the missing ingredient where we'd
+            // like it, unless we don't care where it goes.
+            if !self.picky && idx > 0 {
+                return Some(Box::new(Add(desired[idx].clone(), Relative::Top)));
+            }
+            let between = idx
+                .checked_sub(1)
+                .and_then(|prev| Some((desired.get(prev)?, desired.get(idx + 1)?)))
+                .map(|(a, b)| Relative::Between(a.clone(), b.clone()))
+                .filter(|rel| rel.index(&result.ingredients).is_some());
             let rel = if idx == 0 {
-                // Find first ingredient that comes after the missing one in our desires.
-                let after = self
-                    .desired
-                    .ingredients
-                    .iter()
-                    .filter(|x| result.ingredients.contains(x))
-                    .next();
-                after.map(|b| Relative::Before(b.clone()))
+                Some(Relative::Bottom)
+            } else if idx == 1 && result.ingredients.first() == desired.first() {
+                Some(Relative::OnTopOfBase)
+            } else if between.is_some() {
+                // Both neighbors are already there, so squeeze it in between them.
+                between
             } else {
                 // Find last ingredient in the result that comes before the missing one in our desires.
                 let before = self
@@ -740,15 +1670,29 @@ impl Order {
             // If the allergy is severe and we aren't shy about it, ask for that
             // ingredient to be removed.
             if rng.gen_bool(allergen.severity)
-                && !rng.gen_bool(personality.shyness / personality.stress())
-                && rng.gen_bool((personality.adverbs * 1.5).min(0.99))
+                && !rng.gen_bool((personality.shyness / personality.stress()).min(0.99))
+                && rng.gen_bool((Adverbs.fluency(personality) * 1.5).min(0.99))
             {
                 return Some(Box::new(Remove(allergen.ingredient.clone())));
             }
         }
 
-        // TODO Change my mind about what I want based on my favorites.
+        // Change my mind about what I want based on my favorites and recent meals.
         if rng.gen_bool((personality.spontaneity * personality.stress()).min(0.9)) {
+            // Quietly drop something we're sick of, as long as it isn't on there yet.
+            let last = self.desired.ingredients.len().saturating_sub(1);
+            let bored = self
+                .desired
+                .ingredients
+                .iter()
+                .enumerate()
+                .position(|(i, x)| {
+                    i > 0 && i < last && personality.bored_of(x) && !result.ingredients.contains(x)
+                });
+            if let Some(idx) = bored {
+                let x = self.desired.ingredients.remove(idx);
+                println!("Bored of {}, so never mind", x.name);
+            }
             // If our previous desires contain too few of our favorites, then
             // add one in.
             let any_favs = self.desired.ingredients.iter().any(|x| {
@@ -765,10 +1709,17 @@ impl Order {
                 let dist =
                     WeightedIndex::new(weights).expect("Unable to make favorites distribution");
                 let pick = dist.sample(&mut rng);
-                return Some(Box::new(Add(
-                    personality.preferences[pick].ingredient.clone(),
-                    Relative::Top,
-                )));
+                return Some(
+                    self.want_more(personality.preferences[pick].ingredient.clone(), result),
+                );
+            }
+            // Or something from a recent meal we liked that we aren't getting yet.
+            let craving = personality
+                .cravings()
+                .into_iter()
+                .find(|x| !self.desired.ingredients.contains(x));
+            if let Some(x) = craving {
+                return Some(self.want_more(x.clone(), result));
             }
         }
 
@@ -776,7 +1727,7 @@ impl Order {
         next_ingr.map(|next_ingr| {
             // Maybe ask if they have the ingredient we want.
             if !self.desired.ensured.contains(next_ingr)
-                && rng.gen_bool(personality.shyness / personality.stress())
+                && rng.gen_bool((personality.shyness / personality.stress()).min(0.99))
             {
                 return Box::new(CheckFor(next_ingr.clone())) as Box<dyn Operation>;
             }
@@ -793,7 +1744,7 @@ impl Order {
                 .skip(next_idx) // If we want index 1, skip just the zeroth.
                 .take_while(|x| x == &next_ingr)
                 .count();
-            if same_count > 1 && rng.gen_bool(personality.numbers) {
+            if same_count > 1 && rng.gen_bool(Numbers.fluency(personality)) {
                 Box::new(Repeat(same_count as u32, adder)) as Box<dyn Operation>
             } else {
                 // Default behavior, just add the next ingredient to the top of the sandwich.
@@ -803,15 +1754,22 @@ impl Order {
     }
 }
 
-/// A single message of text and/or a sandwich.
+/// A single message of text and/or a sandwich, along with word of whatever
+/// event the sender is caught up in.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Message {
     pub text: Option<String>,
     pub sandwich: Option<Sandwich>,
+    #[serde(default)]
+    pub event: Option<EventNotice>,
 }
 impl Message {
     pub fn new(text: Option<String>, sandwich: Option<Sandwich>) -> Self {
-        Self { text, sandwich }
+        Self {
+            text,
+            sandwich,
+            event: None,
+        }
     }
     /// Max size in bytes of a message.
     const MAX_SIZE: usize = 4096;
@@ -832,14 +1790,21 @@ impl Message {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct DispatchMessage {
-    pub key: Button,
+/// Something dispatch tells a machine: a key pressed at the dispatch window,
+/// an event making its way through the room, or a cue from the timeline.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum DispatchMessage {
+    Key(Button),
+    Event(EventNotice),
+    /// Grow fonder of the named ingredient.
+    Prefer(String),
+    /// Take a fresh delivery.
+    Restock,
+    /// Close up for the night, going dark until told to wake.
+    Sleep,
+    Wake,
 }
 impl DispatchMessage {
-    pub fn new(key: Button) -> Self {
-        Self { key }
-    }
     /// Max size in bytes of a message.
     const MAX_SIZE: usize = 512;
     pub async fn recv(stream: &mut TcpStream) -> anyhow::Result<DispatchMessage> {
@@ -858,3 +1823,52 @@ impl DispatchMessage {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saying "no" to a change of portion puts back the portion the layer
+    /// had, rather than a normal one.
+    #[test]
+    fn undo_restores_portion() {
+        let mut lang = Personality::fluent(1.0);
+        let cheese = lang
+            .dictionary
+            .ingredients
+            .from_def("cheddar-cheese")
+            .unwrap()
+            .clone();
+        lang.inventory.insert(cheese.name.clone(), 100.0);
+        let before = Sandwich {
+            ingredients: vec![cheese.with_portion(Portion::Light)],
+            ..Default::default()
+        };
+        let op = SetPortion(cheese.with_portion(Portion::Double));
+        let after = op.apply(before.clone(), &mut lang);
+        assert_eq!(after.ingredients[0].portion, Portion::Double);
+        let undone = op.undo(&before).apply(after, &mut lang);
+        assert!(undone.same_layers(&before), "{:?}", undone);
+    }
+
+    /// Same goes for cooking, so taking back "grilled" leaves it toasted.
+    #[test]
+    fn undo_restores_preparation() {
+        let mut lang = Personality::fluent(1.0);
+        let cheese = lang
+            .dictionary
+            .ingredients
+            .from_def("cheddar-cheese")
+            .unwrap()
+            .clone();
+        let before = Sandwich {
+            ingredients: vec![cheese.with_preparation(Preparation::Toasted)],
+            ..Default::default()
+        };
+        let op = Prepare(cheese.with_preparation(Preparation::Grilled));
+        let after = op.apply(before.clone(), &mut lang);
+        assert_eq!(after.ingredients[0].preparation, Preparation::Grilled);
+        let undone = op.undo(&before).apply(after, &mut lang);
+        assert!(undone.same_layers(&before), "{:?}", undone);
+    }
+}
@@ -1,62 +1,162 @@
 use crate::{
+    behavior::{Event, EventNotice, Operation},
+    change,
     display::{setup_display, Display, Render, RenderSender},
     grammar::{
         AnnotatedPhrase, Dictionary, DictionaryEntry, MeaningCloud, Weights, WordFunction,
         DEFAULT_WORD_MAP,
     },
-    sandwich::{Ingredient, Sandwich, BG_COLORS},
+    sandwich::{Ingredient, Portion, Preparation, Sandwich, BG_COLORS},
 };
 use itertools::Itertools;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, time::Duration, time::Instant};
-
-type Inventory = HashMap<String, usize>;
-
-#[derive(Default)]
-pub struct Language {
-    // Weights for grammar rules!
-    pub adverbs: i32,
-    pub adverb_side: i32,
-    pub adposition: i32,
-    pub conjunction: i32,
-    pub numbers: i32,
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    fs::File,
+    time::Duration,
+    time::Instant,
+};
+
+/// Counted in normal portions, so a light layer only takes half of one.
+type Inventory = HashMap<String, f64>;
+
+/// How far an inherited weight may drift from the parent's in either direction.
+const INHERITED_DRIFT: f64 = 0.1;
+/// Range for how much of each learned word association survives into the next generation.
+const INHERITED_MEMORY: (f64, f64) = (0.5, 1.0);
+/// How much spite a "please" takes away.
+const POLITE_RELIEF: f64 = 0.1;
+/// How much spite builds up from each curt request that makes us work.
+const CURT_IRRITATION: f64 = 0.02;
+/// How much spite builds up when they have us take back something we made.
+const REMOVAL_IRRITATION: f64 = 0.04;
+/// How much spite builds up per correction in a row, so a string of them stings more.
+const CORRECTION_IRRITATION: f64 = 0.03;
+/// Past this much spite, we swap in something they don't like rather than
+/// just putting things in the wrong place.
+const SPITE_SUBSTITUTION: f64 = 0.5;
+/// Past this much spite, we do the exact opposite of what they asked.
+const SPITE_REVERSAL: f64 = 0.8;
+/// Longest we'll drag our feet before answering when at our most spiteful.
+const SPITE_DELAY_MS: f64 = 3000.0;
+/// Scales laziness into the chance of botching any one request.
+const LAZY_MISTAKE_RATE: f64 = 0.2;
+/// How many of our last meals shape cravings and boredom.
+const RECENT_MEALS: usize = 5;
+/// Having an ingredient in this many recent meals gets old.
+const BOREDOM_REPEATS: usize = 3;
+/// Chance to want each ingredient again from a recent meal we enjoyed.
+const CRAVING_CHANCE: f64 = 0.3;
+/// How much each bite of a layer sways our taste for it.
+const TASTE_STEP: f64 = 0.05;
+/// How much we can't stand whatever gave us an allergic reaction.
+const REACTION_AVERSION: f64 = 0.9;
+/// Tastes fainter than this are forgotten.
+const TASTE_FLOOR: f64 = 0.02;
+/// Most favorites or aversions we keep track of at once.
+const MAX_TASTES: usize = 8;
+/// Chance that a machine can only ever get a little of any one ingredient.
+const SCARCITY: f64 = 0.2;
+/// Chance to stop by a machine anyway, even though it's out of our favorites.
+const SHUNNED_VISIT_CHANCE: f64 = 0.2;
+/// How much of an ingredient changes hands in one trade.
+pub const TRADE_AMOUNT: f64 = 3.0;
+/// Below this much of an ingredient, we'd like to trade for more.
+const LOW_STOCK: f64 = 2.0;
+/// What a portion of anything costs with the shelves fully stocked.
+const BASE_PRICE: f64 = 1.0;
+/// How much more a portion costs once we're down to the last of it.
+const SCARCITY_MARKUP: f64 = 3.0;
+/// Money every machine starts out with.
+const STARTING_WALLET: u32 = 30;
+/// Below this much money, worrying about it stresses us out.
+const LOW_FUNDS: u32 = 10;
+/// How much being short on money multiplies our stress.
+const BROKE_STRESS: f64 = 1.5;
+/// How much spite builds up from going entirely unpaid for an order.
+const STIFFED_IRRITATION: f64 = 0.2;
+
+/// A single grammar parameter that speakers learn, identified by name so that
+/// new features can bring their own without touching [Language] or [Personality].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Skill {
+    pub id: &'static str,
+    /// Where a newborn speaker starts out.
+    pub initial: f64,
+    /// Whether this skill fades without practice. Word orders only lean one
+    /// way or the other, so they don't.
+    pub fades: bool,
 }
-impl std::ops::Add for Language {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
+impl Skill {
+    /// How well a grammatical feature is understood.
+    pub const fn fluency(id: &'static str, initial: f64) -> Self {
+        Self {
+            id,
+            initial,
+            fades: true,
+        }
+    }
+    /// How likely a word is to go on the usual side of its head.
+    pub const fn word_order(id: &'static str, initial: f64) -> Self {
         Self {
-            adverbs: self.adverbs + rhs.adverbs,
-            adposition: self.adposition + rhs.adposition,
-            conjunction: self.conjunction + rhs.conjunction,
-            numbers: self.numbers + rhs.numbers,
-            adverb_side: self.adverb_side + rhs.adverb_side,
+            id,
+            initial,
+            fades: false,
         }
     }
 }
 
-// #[derive(Serialize, Deserialize)]
-pub enum Event {
-    LunchRush(Instant),
+/// How much a phrase exercised each grammar skill.
+#[derive(Default, Clone, Debug)]
+pub struct Language(Vec<(Skill, i32)>);
+impl Language {
+    pub fn new(skill: Skill, amount: i32) -> Self {
+        Self(vec![(skill, amount)])
+    }
+    /// Replace whatever amount we had for the given skill.
+    pub fn with(mut self, skill: Skill, amount: i32) -> Self {
+        self.0.retain(|(s, _)| s.id != skill.id);
+        self.0.push((skill, amount));
+        self
+    }
+    pub fn get(&self, skill: Skill) -> i32 {
+        self.0
+            .iter()
+            .find(|(s, _)| s.id == skill.id)
+            .map_or(0, |(_, x)| *x)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &(Skill, i32)> {
+        self.0.iter()
+    }
 }
-impl Event {
-    pub fn duration(&self) -> Duration {
-        use Event::*;
-        match self {
-            // Lunch rush lasts an hour.
-            LunchRush(_) => Duration::from_secs(3600),
-        }
+impl From<Skill> for Language {
+    fn from(skill: Skill) -> Self {
+        Self::new(skill, 1)
     }
-    pub fn stress(&self) -> f64 {
-        use Event::*;
-        match self {
-            LunchRush(_) => 2.0,
+}
+impl std::ops::Add for Language {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut sum = self;
+        for (skill, amount) in rhs.0 {
+            let total = sum.get(skill) + amount;
+            sum = sum.with(skill, total);
         }
+        sum
     }
-    pub fn is_over(&self) -> bool {
-        match self {
-            Event::LunchRush(started) => Instant::now().duration_since(*started) > self.duration(),
-        }
+}
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(skill, x)| format!("{} {:+}", skill.id, x))
+                .join(", ")
+        )
     }
 }
 
@@ -75,23 +175,41 @@ pub struct Personality {
     pub spite: f64,
     pub planned: f64,
     pub spontaneity: f64,
+    /// Likeliness to care how the sandwich is stacked, rather than just what's on it.
     pub order_sensitivity: f64,
     pub allergies: Vec<Preference>,
     pub preferences: Vec<Preference>,
-    // Weights for grammar rules!
-    pub adverbs: f64,
-    pub adverb_side: f64,
-    pub adposition: f64,
-    pub conjunction: f64,
-    pub numbers: f64,
+    /// Ingredients we've come to dislike, which we'd rather leave off.
+    #[serde(default)]
+    pub aversions: Vec<Preference>,
+    /// Weights for grammar rules, keyed by [Skill] identifier.
+    /// Skills we haven't touched yet sit at their initial value.
+    #[serde(default)]
+    pub grammar: BTreeMap<String, f64>,
     pub pitch_shift: f64,
-    /// Maps ingredient names to their inventory count.
-    /// Doesn't save between sessions.
-    #[serde(skip)]
+    /// Maps ingredient names to how much of each we have in stock.
+    #[serde(default)]
     pub inventory: Inventory,
-    pub history: Vec<Sandwich>,
+    /// How much of each ingredient a delivery brings us, which differs from
+    /// machine to machine.
+    #[serde(default)]
+    pub supply: Inventory,
+    /// How many orders we serve between deliveries.
+    #[serde(default = "default_restock_interval")]
+    pub restock_interval: u32,
+    #[serde(default)]
+    pub orders_since_restock: u32,
+    /// Money we have on hand to pay for sandwiches, earned by serving them.
+    #[serde(default = "default_wallet")]
+    pub wallet: u32,
+    /// Ingredients each machine has told us they're out of, by hostname.
+    #[serde(default)]
+    pub sold_out: HashMap<String, HashSet<String>>,
+    pub history: Vec<Meal>,
     pub cloud: MeaningCloud,
-    #[serde(skip)]
+    /// Whatever's going on in the room right now, kept across restarts so
+    /// that its trait changes still get undone.
+    #[serde(default)]
     pub event: Option<Event>,
     #[serde(skip, default = "Dictionary::new")]
     pub dictionary: Dictionary,
@@ -99,68 +217,397 @@ pub struct Personality {
     pub display: Display,
     #[serde(skip)]
     pub last_lex: Option<AnnotatedPhrase>,
+    /// Takes back our last move if the other machine says "no" to it.
+    #[serde(skip)]
+    pub undo: Option<Box<dyn Operation>>,
+    /// Whether we're behind the counter making someone's order, which is the
+    /// only time our laziness shows.
+    #[serde(skip)]
+    pub serving: bool,
+    /// Whether we've closed up for the night, leaving our screen dark.
+    #[serde(skip)]
+    pub asleep: bool,
+    #[serde(default)]
+    pub lineage: Lineage,
+    /// Conversations since we last used each grammar skill, keyed by [Skill] identifier.
+    #[serde(default)]
+    pub skill_idle: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub decay: Decay,
+    #[serde(skip)]
+    last_decay: Option<Instant>,
 }
 impl Personality {
     pub fn new() -> Self {
+        Self::with_display(setup_display())
+    }
+
+    fn with_display(display: Display) -> Self {
         let mut rng = thread_rng();
         let dictionary = Dictionary::new();
+        let supply = Self::random_supply(&dictionary);
         Self {
-            display: setup_display(),
+            display,
             planned: rng.gen_range(0.1, 0.9),
             laziness: rng.gen_range(0.1, 0.9),
             forgetfulness: rng.gen_range(0.1, 0.9),
             politeness: rng.gen_range(0.1, 0.9),
             shyness: rng.gen_range(0.1, 0.9),
             spite: 0.0,
-            order_sensitivity: 1.0,
+            order_sensitivity: rng.gen_range(0.1, 0.9),
             spontaneity: rng.gen_range(0.1, 0.9),
             allergies: vec![Preference {
                 severity: 0.6,
                 ingredient: dictionary.ingredients.random().clone(),
             }],
             // TODO Add preferences for other ingredients starting at zero??
+            // Some machines like their favorite cooked a certain way.
             preferences: vec![Preference {
                 severity: 0.8,
-                ingredient: dictionary.ingredients.random().clone(),
+                ingredient: dictionary
+                    .ingredients
+                    .random()
+                    .with_preparation(*Preparation::ALL.choose(&mut rng).unwrap()),
             }],
+            aversions: Vec::new(),
             // Fill our cloud with equal weights on every definition for all words.
             cloud: Default::default(),
             // Grammar rule weights
-            adverbs: 0.1,
-            adverb_side: 0.95,
-            adposition: 0.1,
-            conjunction: 0.1,
-            numbers: 0.1,
+            grammar: change::all_skills()
+                .map(|s| (s.id.to_owned(), s.initial))
+                .collect(),
             pitch_shift: thread_rng().gen_range(0.3, 1.7),
-            inventory: Self::default_inventory(&dictionary),
+            inventory: supply.clone(),
+            supply,
+            restock_interval: rng.gen_range(3, 10),
+            orders_since_restock: 0,
+            wallet: STARTING_WALLET,
+            sold_out: HashMap::new(),
             dictionary,
             last_lex: None,
+            undo: None,
+            serving: false,
+            asleep: false,
             history: Vec::new(),
             event: None,
+            lineage: Lineage::default(),
+            skill_idle: BTreeMap::new(),
+            decay: Decay::default(),
+            last_decay: None,
+        }
+    }
+
+    /// Someone fluent in every grammar feature, who always puts words on the
+    /// usual side of their head at an `order` of one and the other side at
+    /// zero. It never opens a window, so tests can use it.
+    #[cfg(test)]
+    pub fn fluent(order: f64) -> Self {
+        let mut lang = Self::with_display(Display::headless());
+        for skill in change::all_skills() {
+            let x = if skill.fades { 1.0 } else { order };
+            lang.grammar.insert(skill.id.to_owned(), x);
+        }
+        lang
+    }
+
+    /// Replace this personality with a newborn one, which inherits a mutated
+    /// copy of our language and tastes rather than starting from scratch.
+    pub fn reincarnate(&mut self, cause_of_death: &Ingredient) {
+        let mut rng = thread_rng();
+        let mut child = Self::with_display(Display::headless());
+        // The machine keeps its screen, only the mind inside is new.
+        std::mem::swap(&mut child.display, &mut self.display);
+
+        // Children only remember part of what their parent learned about each word.
+        child.cloud = self
+            .cloud
+            .iter()
+            .map(|(word, weights)| {
+                let weights = weights
+                    .iter()
+                    .map(|(entry, w)| {
+                        let kept = rng.gen_range(INHERITED_MEMORY.0, INHERITED_MEMORY.1);
+                        (entry.clone(), 1 + ((w - 1) as f64 * kept).round() as u32)
+                    })
+                    .collect();
+                (word.clone(), weights)
+            })
+            .collect();
+
+        // Grammar habits carry over, give or take.
+        child.grammar = self
+            .grammar
+            .iter()
+            .map(|(id, x)| (id.clone(), Self::drift(*x, &mut rng)))
+            .collect();
+        // Along with how long each has gone unused, so a skill the parent was
+        // already neglecting doesn't get a fresh grace period, and however
+        // this machine's forgetting was tuned.
+        child.skill_idle = self.skill_idle.clone();
+        child.decay = self.decay.clone();
+
+        // So do our favorite ingredients, and the ones we can't stand.
+        child.preferences = self
+            .preferences
+            .iter()
+            .filter(|p| p.ingredient != *cause_of_death)
+            .map(|p| Preference {
+                ingredient: p.ingredient.clone(),
+                severity: Self::drift(p.severity, &mut rng),
+            })
+            .collect();
+        child.aversions = self
+            .aversions
+            .iter()
+            .map(|p| Preference {
+                ingredient: p.ingredient.clone(),
+                severity: Self::drift(p.severity, &mut rng),
+            })
+            .collect();
+        // Whatever did us in leaves a bad taste for the next life.
+        child.acquire_taste(cause_of_death, -REACTION_AVERSION);
+
+        // The shelves and deliveries belong to the machine, not the mind.
+        child.inventory = std::mem::take(&mut self.inventory);
+        child.supply = std::mem::take(&mut self.supply);
+        child.restock_interval = self.restock_interval;
+        child.orders_since_restock = self.orders_since_restock;
+        child.wallet = self.wallet;
+
+        child.lineage = self.lineage.clone();
+        child.lineage.generation += 1;
+        child
+            .lineage
+            .causes_of_death
+            .push(cause_of_death.name.clone());
+
+        *self = child;
+    }
+
+    fn drift(x: f64, rng: &mut impl Rng) -> f64 {
+        (x + rng.gen_range(-INHERITED_DRIFT, INHERITED_DRIFT))
+            .max(0.01)
+            .min(1.0)
+    }
+
+    /// Sets off the named event, wrapping up whatever was already going on.
+    pub fn start_event(&mut self, name: &str, strength: f64, age: Duration) {
+        self.end_event();
+        println!("Starting event {} at strength {:.2}", name, strength);
+        self.event = Event::start(name, strength, age, self);
+    }
+
+    /// Gets caught up in an event we heard about, unless we're already in the
+    /// thick of something stronger. Echoes of the event we're in don't start
+    /// it over, and neither does word of one that's already over.
+    pub fn hear_event(&mut self, notice: &EventNotice) {
+        let busy = self.event.as_ref().is_some_and(|e| {
+            !e.is_over()
+                && (e.strength > notice.strength
+                    || (e.name == notice.name && e.strength >= notice.strength))
+        });
+        if !busy && !notice.is_stale() {
+            self.start_event(&notice.name, notice.strength, notice.age());
+        }
+    }
+
+    /// Wraps up the current event, if there is one.
+    pub fn end_event(&mut self) {
+        if let Some(event) = self.event.take() {
+            println!("Ending event {}", event.name);
+            event.end(self);
+        }
+    }
+
+    /// Wraps up the current event once it's run its course.
+    pub fn end_finished_event(&mut self) {
+        if self.event.as_ref().is_some_and(|e| e.is_over()) {
+            self.end_event();
         }
     }
 
     pub fn stress(&self) -> f64 {
-        self.event.as_ref().map(|e| e.stress()).unwrap_or(1.0)
+        let broke = if self.wallet < LOW_FUNDS {
+            BROKE_STRESS
+        } else {
+            1.0
+        };
+        self.event.as_ref().map(|e| e.stress()).unwrap_or(1.0) * broke
     }
 
-    fn default_inventory(dict: &Dictionary) -> Inventory {
-        const DEFAULT_INGREDIENT_COUNT: usize = 20;
+    /// Each machine gets plenty of most things, but only a trickle of a few.
+    fn random_supply(dict: &Dictionary) -> Inventory {
+        let mut rng = thread_rng();
         // Grab all the bottom-level ingredients.
-        let ingredients = dict.ingredients.leaves();
-        ingredients
+        dict.ingredients
+            .leaves()
+            .into_iter()
+            .map(|(name, _)| {
+                let amount = if rng.gen_bool(SCARCITY) {
+                    rng.gen_range(1, 4)
+                } else {
+                    rng.gen_range(10, 30)
+                };
+                (name, amount as f64)
+            })
+            .collect()
+    }
+
+    /// Fill the shelves back up with a fresh delivery.
+    pub fn restock(&mut self) {
+        self.inventory = self.supply.clone();
+        self.orders_since_restock = 0;
+    }
+
+    /// Count off another order to serve, taking a delivery if one's due.
+    pub fn start_shift(&mut self) {
+        self.orders_since_restock += 1;
+        if self.orders_since_restock >= self.restock_interval {
+            println!("Restocking!");
+            self.restock();
+        }
+    }
+
+    /// Every ingredient we've run out of.
+    pub fn sold_out_items(&self) -> Vec<Ingredient> {
+        self.inventory
             .iter()
-            // Provide a few for each one.
-            .map(|(name, _)| (name.clone(), DEFAULT_INGREDIENT_COUNT))
+            .filter(|(_, count)| **count <= 0.0)
+            .filter_map(|(name, _)| self.dictionary.ingredients.from_def(name))
+            .cloned()
             .collect()
     }
 
-    pub fn reset_inventory(&mut self) {
-        self.inventory = Self::default_inventory(&self.dictionary);
+    /// Remember that a machine told us they're out of something.
+    pub fn hear_sold_out(&mut self, host: &str, ingredient: &Ingredient) {
+        self.sold_out
+            .entry(host.to_owned())
+            .or_default()
+            .insert(ingredient.name.clone());
+    }
+
+    /// Whether we could hand over a trade's worth of an ingredient without
+    /// running low ourselves.
+    pub fn can_spare(&self, ingredient: &Ingredient) -> bool {
+        *self.inventory.get(&ingredient.name).unwrap_or(&0.0) >= LOW_STOCK + TRADE_AMOUNT
+    }
+
+    /// Puts some amount of an ingredient into stock, like from a trade.
+    pub fn receive(&mut self, ingredient: &Ingredient, amount: f64) {
+        *self.inventory.entry(ingredient.name.clone()).or_default() += amount;
+    }
+
+    /// Everything we're running low on and would trade for, in random order.
+    pub fn trade_wants(&self) -> Vec<Ingredient> {
+        let mut wants: Vec<_> = self
+            .inventory
+            .iter()
+            .filter(|(_, count)| **count < LOW_STOCK)
+            .filter_map(|(name, _)| self.dictionary.ingredients.from_def(name))
+            .cloned()
+            .collect();
+        wants.shuffle(&mut thread_rng());
+        wants
+    }
+
+    /// Whatever we have the most of, if we can spare any of it.
+    pub fn surplus(&self) -> Option<Ingredient> {
+        self.inventory
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .and_then(|(name, _)| self.dictionary.ingredients.from_def(name))
+            .filter(|x| self.can_spare(x))
+            .cloned()
+    }
+
+    /// What a portion of an ingredient goes for here, dearer the less of our
+    /// last delivery we have left. Customers only know prices by their own
+    /// shelves, so they use this to guess what an order will cost them too.
+    pub fn price(&self, ingredient: &Ingredient) -> u32 {
+        let stock = *self.inventory.get(&ingredient.name).unwrap_or(&0.0);
+        let supply = self.supply.get(&ingredient.name).unwrap_or(&1.0).max(1.0);
+        let scarcity = (1.0 - stock / supply).clamp(0.0, 1.0);
+        ((BASE_PRICE + SCARCITY_MARKUP * scarcity) * ingredient.portion.amount()).round() as u32
+    }
+
+    /// What a whole sandwich goes for here.
+    pub fn price_of(&self, sandwich: &Sandwich) -> u32 {
+        sandwich.ingredients.iter().map(|x| self.price(x)).sum()
+    }
+
+    /// Trims a sandwich down until we can afford it, swapping the priciest
+    /// filling for something cheaper nearby or else going without. Favorites
+    /// are the last thing we give up.
+    pub fn within_budget(&self, mut sandwich: Sandwich) -> Sandwich {
+        while self.price_of(&sandwich) > self.wallet {
+            // Leave the bread alone.
+            let fillings = 1..sandwich.ingredients.len().saturating_sub(1);
+            let idx = match fillings.max_by_key(|&i| {
+                let x = &sandwich.ingredients[i];
+                (!self.likes(x), self.price(x))
+            }) {
+                Some(idx) => idx,
+                None => break,
+            };
+            let priciest = sandwich.ingredients[idx].clone();
+            let cheaper = self
+                .dictionary
+                .ingredients
+                .neighbors(&priciest)
+                .into_iter()
+                .map(|x| {
+                    x.with_portion(priciest.portion)
+                        .with_preparation(priciest.preparation)
+                })
+                .filter(|x| self.price(x) < self.price(&priciest))
+                .min_by_key(|x| self.price(x));
+            match cheaper {
+                Some(x) => {
+                    println!("Can't afford {}, getting {} instead", priciest.name, x.name);
+                    sandwich.ingredients[idx] = x;
+                }
+                None => {
+                    println!("Can't afford {}, going without", priciest.name);
+                    sandwich.ingredients.remove(idx);
+                }
+            }
+        }
+        sandwich
     }
 
-    pub fn total_inventory_count(&self) -> usize {
-        self.inventory.iter().map(|(_, count)| count).sum()
+    /// Whether this ingredient is one of our favorites.
+    fn likes(&self, ingredient: &Ingredient) -> bool {
+        self.preferences.iter().any(|p| p.ingredient == *ingredient)
+    }
+
+    /// Sours on customers who come up short on their bill.
+    pub fn hear_payment(&mut self, bill: u32, paid: u32) {
+        if bill > paid {
+            let shortfall = (bill - paid) as f64 / bill as f64;
+            self.spite = (self.spite + shortfall * STIFFED_IRRITATION).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Anything a machine put on our sandwich, they clearly have again.
+    pub fn got_from(&mut self, host: &str, sandwich: &Sandwich) {
+        if let Some(out) = self.sold_out.get_mut(host) {
+            for x in &sandwich.ingredients {
+                out.remove(&x.name);
+            }
+        }
+    }
+
+    /// Whether we'd mostly rather go elsewhere, since this machine was out
+    /// of one of our favorites last we heard.
+    pub fn shuns(&self, host: &str) -> bool {
+        let out = match self.sold_out.get(host) {
+            Some(out) => out,
+            None => return false,
+        };
+        self.preferences
+            .iter()
+            .any(|p| out.contains(&p.ingredient.name))
+            && !thread_rng().gen_bool(SHUNNED_VISIT_CHANCE)
     }
 
     /// Retrieve the meaning distribution for a particular word.
@@ -184,32 +631,67 @@ impl Personality {
 
     pub fn load() -> anyhow::Result<Self> {
         let f = File::open("personality.yaml")?;
-        Ok(serde_yaml::from_reader(&f)?)
+        let mut p: Self = serde_yaml::from_reader(&f)?;
+        // Older saves didn't keep track of stock.
+        if p.supply.is_empty() {
+            p.supply = Self::random_supply(&p.dictionary);
+        }
+        if p.inventory.is_empty() {
+            p.restock();
+        }
+        Ok(p)
     }
     pub fn save(&self) -> anyhow::Result<()> {
         let mut f = File::create("personality.yaml")?;
         Ok(serde_yaml::to_writer(f, self)?)
     }
+    /// How far along we are with the given grammar skill, from zero to one.
+    pub fn skill(&self, skill: Skill) -> f64 {
+        self.grammar.get(skill.id).copied().unwrap_or(skill.initial)
+    }
+    pub fn skill_mut(&mut self, skill: Skill) -> &mut f64 {
+        self.grammar
+            .entry(skill.id.to_owned())
+            .or_insert(skill.initial)
+    }
+    /// Meant to be called once per conversation, so that grammar we haven't
+    /// used for a while slowly fades away.
     pub fn degrade_language_skills(&mut self) {
-        let factor = 6.0;
-        let deg = |x: &mut f64| {
-            *x = (*x - ((*x * 100.0).ln()) / (factor * 100.0)).max(0.0);
-        };
-        deg(&mut self.adverbs);
-        deg(&mut self.adposition);
-        deg(&mut self.conjunction);
-        deg(&mut self.numbers)
+        let decay = self.decay.clone();
+        // Time spent without anyone to talk to wears on us too.
+        let hours = self
+            .last_decay
+            .map_or(0.0, |t| t.elapsed().as_secs_f64() / 3600.0);
+        self.last_decay = Some(Instant::now());
+
+        for skill in change::all_skills().filter(|s| s.fades) {
+            let idle = self.skill_idle.entry(skill.id.to_owned()).or_insert(0);
+            *idle += 1;
+            let neglect = idle.saturating_sub(decay.grace) as f64;
+            let strength = neglect.powf(decay.curve) + hours * decay.hourly;
+            let x = self.skill_mut(skill);
+            // Skills never quite fade away entirely, which also keeps the
+            // logarithm below from flipping sign.
+            if strength > 0.0 && *x > 0.01 {
+                let orig = *x;
+                *x = (*x - strength * (*x * 100.0).ln() / (decay.factor * 100.0)).clamp(0.01, 1.0);
+                println!("Degraded {} from {} => {}", skill.id, orig, *x);
+            }
+        }
     }
     pub fn apply_upgrade(&mut self, lang: Language) {
-        Self::upgrade_skill(&mut self.adposition, lang.adposition as f64);
-        Self::upgrade_skill(&mut self.numbers, lang.numbers as f64);
-        Self::upgrade_skill(&mut self.adverbs, lang.adverbs as f64);
-        Self::upgrade_skill(&mut self.conjunction, lang.conjunction as f64);
-        Self::upgrade_skill(&mut self.adverb_side, lang.adverb_side as f64);
+        println!("Exercised {}", lang);
+        for (skill, amount) in lang.iter() {
+            if *amount != 0 {
+                self.skill_idle.insert(skill.id.to_owned(), 0);
+            }
+            Self::upgrade_skill(self.skill_mut(*skill), *amount as f64);
+        }
     }
     pub fn upgrade_skill(x: &mut f64, mult: f64) {
         let orig = *x;
-        *x = (*x + (x.ln() / 100.0).abs() * mult).min(1.0);
+        // Word order drifts both ways, so skills can be trained down too.
+        *x = (*x + (x.ln() / 100.0).abs() * mult).clamp(0.01, 1.0);
         println!("Upgraded language skill from {} => {}", orig, *x);
     }
     pub fn render(&self, state: Render) -> anyhow::Result<()> {
@@ -223,26 +705,171 @@ impl Personality {
             .find(|x| &x.ingredient.name == name)
         {
             Self::upgrade_skill(&mut pref.severity, 1.0);
+            return;
         }
         // Otherwise, add a new preference with the base severity.
-        self.preferences.push(Preference {
-            ingredient: self
-                .dictionary
-                .ingredients
-                .from_def(name)
-                .expect("No ingredient by that name")
-                .clone(),
-            severity: 0.1,
-        });
+        match self.dictionary.ingredients.from_def(name) {
+            Some(ingredient) => self.preferences.push(Preference {
+                ingredient: ingredient.clone(),
+                severity: 0.1,
+            }),
+            None => println!("No ingredient called {} to prefer", name),
+        }
+    }
+    /// Nudges how much we like an ingredient, for better or worse. Liking
+    /// something wears down any aversion to it first, and vice versa.
+    pub fn acquire_taste(&mut self, ingredient: &Ingredient, amount: f64) {
+        let (toward, away) = if amount >= 0.0 {
+            (&mut self.preferences, &mut self.aversions)
+        } else {
+            (&mut self.aversions, &mut self.preferences)
+        };
+        let amount = amount.abs();
+        let leftover = match away.iter_mut().find(|x| x.ingredient == *ingredient) {
+            Some(x) => {
+                let spent = amount.min(x.severity);
+                x.severity -= spent;
+                amount - spent
+            }
+            None => amount,
+        };
+        if leftover > 0.0 {
+            match toward.iter_mut().find(|x| x.ingredient == *ingredient) {
+                Some(x) => x.severity = (x.severity + leftover).min(1.0),
+                None => toward.push(Preference {
+                    ingredient: ingredient.clone(),
+                    severity: leftover.min(1.0),
+                }),
+            }
+        }
+        self.normalize_tastes();
+    }
+
+    /// Forgets faint tastes and keeps only our strongest few, strongest first.
+    fn normalize_tastes(&mut self) {
+        for tastes in &mut [&mut self.preferences, &mut self.aversions] {
+            tastes.retain(|x| x.severity >= TASTE_FLOOR);
+            tastes.sort_by(|a, b| b.severity.partial_cmp(&a.severity).unwrap());
+            tastes.truncate(MAX_TASTES);
+        }
+    }
+
+    /// Takes a bite of one layer of a sandwich we ordered, liking it more if
+    /// it's just what we asked for and less if we never wanted it.
+    pub fn taste(&mut self, layer: &Ingredient, desired: &Sandwich) {
+        let asked = desired.ingredients.iter().find(|x| *x == layer);
+        match asked {
+            Some(x) if x.same_style(layer) => self.acquire_taste(layer, TASTE_STEP),
+            Some(_) => {}
+            None => self.acquire_taste(layer, -TASTE_STEP),
+        }
+    }
+
+    /// Saying please soothes us, while curt demands for more work, taking
+    /// back what we made, and correcting us again and again wear on our
+    /// patience.
+    pub fn hear_request(
+        &mut self,
+        polite: bool,
+        before: &Sandwich,
+        after: &Sandwich,
+        corrections: u32,
+    ) {
+        let mut irritation = CORRECTION_IRRITATION * corrections as f64;
+        if after.ingredients.len() < before.ingredients.len() {
+            irritation += REMOVAL_IRRITATION;
+        }
+        if polite {
+            irritation -= POLITE_RELIEF;
+        } else if !after.same_layers(before) {
+            irritation += CURT_IRRITATION;
+        }
+        self.spite = (self.spite + irritation).clamp(0.0, 1.0);
+    }
+
+    /// Whether we're fed up enough to throw out their request and do the
+    /// opposite. Doing so lets out all our anger at once.
+    pub fn spite_reversal(&mut self, stress: f64) -> bool {
+        let reverse =
+            self.spite >= SPITE_REVERSAL && thread_rng().gen_bool((self.spite * stress).min(0.99));
+        if reverse {
+            self.spite = 0.0;
+        }
+        reverse
+    }
+
+    /// Pettier ways to get back at a customer than a full reversal, taken out
+    /// on the layer they just had us add. The more spite, the meaner we get:
+    /// first putting it in the wrong place, then swapping in something they
+    /// had us take off earlier. Each act lets out a bit of our anger.
+    pub fn act_out(
+        &mut self,
+        before: &Sandwich,
+        after: Sandwich,
+        disliked: &[Ingredient],
+        stress: f64,
+    ) -> Sandwich {
+        let mut rng = thread_rng();
+        if !rng.gen_bool((self.spite * stress).min(0.99))
+            || after.ingredients.len() != before.ingredients.len() + 1
+        {
+            return after;
+        }
+        let added = after
+            .ingredients
+            .iter()
+            .zip(&before.ingredients)
+            .position(|(a, b)| a != b)
+            .unwrap_or(before.ingredients.len());
+        let mut ingredients = after.ingredients;
+        let substitute = disliked
+            .iter()
+            .filter(|x| self.has_ingredient(x))
+            .choose(&mut rng)
+            .cloned();
+        match substitute {
+            Some(x) if self.spite >= SPITE_SUBSTITUTION => {
+                println!("Spitefully swapping in {}", x.name);
+                self.use_ingredient(&x);
+                ingredients[added] = x;
+                self.spite /= 2.0;
+            }
+            _ => {
+                let layer = ingredients.remove(added);
+                let idx = (0..=ingredients.len())
+                    .filter(|i| *i != added)
+                    .choose(&mut rng)
+                    .unwrap_or(added);
+                println!("Spitefully putting {} at {}", layer.name, idx);
+                ingredients.insert(idx, layer);
+                self.spite *= 0.8;
+            }
+        }
+        Sandwich {
+            ingredients,
+            ..after
+        }
+    }
+
+    /// How long we drag our feet before answering, out of spite.
+    pub fn spite_delay(&self) -> Duration {
+        Duration::from_millis((self.spite * SPITE_DELAY_MS) as u64)
+    }
+    /// Whether we cut a corner on this request while serving.
+    pub fn slacks_off(&self) -> bool {
+        self.serving
+            && thread_rng().gen_bool((self.laziness * self.stress() * LAZY_MISTAKE_RATE).min(0.9))
     }
     pub fn has_ingredient(&self, desired: &Ingredient) -> bool {
-        *self.inventory.get(&desired.name).unwrap_or(&0) > 0
+        *self.inventory.get(&desired.name).unwrap_or(&0.0) > 0.0
     }
     pub fn use_ingredient(&mut self, used: &Ingredient) {
-        let name = used.name.clone();
-        let prev = *self.inventory.get(&name).unwrap_or(&0);
-        if prev > 0 {
-            self.inventory.insert(name, prev - 1);
+        self.use_amount(used, used.portion.amount());
+    }
+    /// Takes some amount of an ingredient out of stock, down to nothing left.
+    pub fn use_amount(&mut self, used: &Ingredient, amount: f64) {
+        if let Some(count) = self.inventory.get_mut(&used.name) {
+            *count = (*count - amount).max(0.0);
         }
     }
     pub fn gen_sandwich(&self, mut len: usize) -> Sandwich {
@@ -257,18 +884,35 @@ impl Personality {
         // Preferences and allergies could override each other applying to the
         // same ingredient.
         for fav in &self.preferences {
-            if rng.gen_bool((fav.severity * self.stress()).min(0.9)) {
+            if !self.reacted_to(&fav.ingredient)
+                && rng.gen_bool((fav.severity * self.stress()).min(0.9))
+            {
                 ingredients.push(fav.ingredient.clone());
+                len = len.saturating_sub(1);
+            }
+        }
+        // Want some of what we liked last time.
+        for crave in self.cravings() {
+            if len > 0 && rng.gen_bool(CRAVING_CHANCE) {
+                ingredients.push(crave.clone());
                 len -= 1;
             }
         }
+        // Fill out the rest with anything we aren't sick of.
         ingredients.extend(
             (0..)
                 .map(|_| self.dictionary.ingredients.random().clone())
+                .filter(|x| !self.avoids(x))
                 // 50% chance for a duplicate ingredient to stay.
                 .unique_by(|x| format!("{}{}", x.name, rng.gen_bool(0.3)))
                 .take(len),
         );
+        // Every so often, ask for more or less of something in the middle.
+        for ingr in ingredients.iter_mut().skip(1) {
+            if rng.gen_bool(0.15) {
+                ingr.portion = *Portion::ALL.choose(&mut rng).unwrap();
+            }
+        }
         ingredients.push(top.clone());
         Sandwich {
             ingredients,
@@ -278,13 +922,52 @@ impl Personality {
         }
     }
 
-    /// Keep just ten of our last sandwiches in a stack.
-    /// We might use those memories later to shape our choices.
-    pub fn eat(&mut self, sandwich: Sandwich) {
-        self.history.insert(0, sandwich);
+    /// Keep just ten of our last meals in a stack, which shape what we order next.
+    pub fn eat(&mut self, meal: Meal) {
+        self.history.insert(0, meal);
         self.history.truncate(10);
     }
 
+    /// Ingredients from recent meals that hit the spot, most recent first.
+    pub fn cravings(&self) -> Vec<&Ingredient> {
+        self.history
+            .iter()
+            .take(RECENT_MEALS)
+            .filter(|m| m.satisfied && m.reaction.is_none())
+            .flat_map(|m| m.fillings())
+            .filter(|x| !self.avoids(x))
+            .unique()
+            .collect()
+    }
+
+    /// Whether we've had this ingredient in too many recent meals.
+    pub fn bored_of(&self, ingredient: &Ingredient) -> bool {
+        self.history
+            .iter()
+            .take(RECENT_MEALS)
+            .filter(|m| m.fillings().any(|x| x == ingredient))
+            .count()
+            >= BOREDOM_REPEATS
+    }
+
+    /// Whether we remember this ingredient making us sick, even if it was a
+    /// past life's last meal.
+    pub fn reacted_to(&self, ingredient: &Ingredient) -> bool {
+        self.history
+            .iter()
+            .any(|m| m.reaction.as_ref() == Some(ingredient))
+    }
+
+    /// Whether we'd rather not have this ingredient right now.
+    pub fn avoids(&self, ingredient: &Ingredient) -> bool {
+        self.reacted_to(ingredient)
+            || self.bored_of(ingredient)
+            || self
+                .aversions
+                .iter()
+                .any(|a| a.ingredient.includes(ingredient) && thread_rng().gen_bool(a.severity))
+    }
+
     pub fn allergic_reaction(&self, ingredient: &Ingredient) -> bool {
         let allergy = self
             .allergies
@@ -296,6 +979,67 @@ impl Personality {
     }
 }
 
+fn default_restock_interval() -> u32 {
+    5
+}
+
+fn default_wallet() -> u32 {
+    STARTING_WALLET
+}
+
+/// Tunes how quickly unused grammar skills fade.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Decay {
+    /// Conversations a skill can go unused before it starts to fade.
+    pub grace: u32,
+    /// Larger factors fade skills more slowly.
+    pub factor: f64,
+    /// How sharply fading speeds up the longer a skill goes unused.
+    /// One means it speeds up linearly, zero keeps it constant.
+    pub curve: f64,
+    /// Extra fading for every hour spent between conversations.
+    pub hourly: f64,
+}
+impl Default for Decay {
+    fn default() -> Self {
+        Self {
+            grace: 5,
+            factor: 6.0,
+            curve: 0.5,
+            hourly: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Lineage {
+    /// How many ancestors this personality has had.
+    pub generation: u32,
+    /// Names of the ingredients that killed each ancestor, oldest first.
+    pub causes_of_death: Vec<String>,
+}
+
+/// A sandwich we ate, and how it went.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Meal {
+    #[serde(flatten)]
+    pub sandwich: Sandwich,
+    /// Whether we got what we ordered.
+    #[serde(default)]
+    pub satisfied: bool,
+    /// The ingredient we had an allergic reaction to, if any.
+    #[serde(default)]
+    pub reaction: Option<Ingredient>,
+}
+impl Meal {
+    /// Everything between the bread.
+    pub fn fillings(&self) -> impl Iterator<Item = &Ingredient> {
+        let layers = &self.sandwich.ingredients;
+        layers.iter().skip(1).take(layers.len().saturating_sub(2))
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Preference {
     pub ingredient: Ingredient,
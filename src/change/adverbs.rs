@@ -0,0 +1,84 @@
+use super::{ordered, Change, ParseResult, Rule};
+use crate::{
+    behavior::{ops, Language, Operation, Personality, Skill},
+    grammar::{word_with_role, AnnotatedPhrase, AnnotatedWord, Parsed, WordFunction, WordRole},
+};
+use nom::{branch::*, sequence::*, IResult};
+use rand::prelude::*;
+
+pub const ADVERBS: Skill = Skill::fluency("adverbs", 0.1);
+/// Whether adverbs go before the phrase they modify.
+pub const ADVERB_SIDE: Skill = Skill::word_order("adverb-side", 0.95);
+
+/// Adverbs modify the whole phrase they're attached to: negation reverses it,
+/// "ever" makes it persist, and questions ask about it instead.
+pub struct Adverbs;
+impl Adverbs {
+    fn react(&self, adv: &AnnotatedWord, (vp, vp_l): Parsed, lang: &Personality) -> Parsed {
+        let op = if thread_rng().gen_bool(self.fluency(lang)) {
+            match adv.definition() {
+                Some(WordFunction::Ever) => Box::new(ops::Persist(vp)) as Box<dyn Operation>,
+                Some(WordFunction::Negation) => vp.reverse(),
+                Some(WordFunction::Question) => vp.question(),
+                _ => vp,
+            }
+        } else {
+            vp
+        };
+        (op, vp_l + self.exercise())
+    }
+}
+impl Change for Adverbs {
+    fn skill(&self) -> Skill {
+        ADVERBS
+    }
+    fn extra_skills(&self) -> &'static [Skill] {
+        &[ADVERB_SIDE]
+    }
+    /// Matches a negated phrase to reverse the inner meaning, either "not A" or just "A".
+    fn parse<'a>(
+        &self,
+        input: &'a [AnnotatedWord],
+        lang: &Personality,
+        this: Rule<'_, 'a>,
+        inner: Rule<'_, 'a>,
+    ) -> ParseResult<'a> {
+        alt((
+            |i| {
+                // Pick a word order based on our language system.
+                if thread_rng().gen_bool(lang.skill(ADVERB_SIDE)) {
+                    let (i, (adv, (vp, vp_l))) = pair(adverb, this)(i)?;
+                    Ok((
+                        i,
+                        self.react(adv, (vp, vp_l + Language::new(ADVERB_SIDE, 1)), lang),
+                    ))
+                } else {
+                    // Trailing adverbs stack up after the phrase, innermost first,
+                    // since starting over with `this` would never consume a word.
+                    let (mut i, (vp, adv)) = pair(inner, adverb)(i)?;
+                    let mut parsed = self.react(adv, vp, lang);
+                    while let Ok((rest, adv)) = adverb(i) {
+                        parsed = self.react(adv, parsed, lang);
+                        i = rest;
+                    }
+                    let (op, l) = parsed;
+                    Ok((i, (op, l + Language::new(ADVERB_SIDE, -1))))
+                }
+            },
+            inner,
+        ))(input)
+    }
+    fn attach(
+        &self,
+        lang: &Personality,
+        head: AnnotatedPhrase,
+        modifier: AnnotatedPhrase,
+    ) -> AnnotatedPhrase {
+        // Speak adverbs on the same side we expect to hear them.
+        ordered(lang, ADVERB_SIDE, modifier, head)
+    }
+}
+
+fn adverb(input: &[AnnotatedWord]) -> IResult<&[AnnotatedWord], &AnnotatedWord> {
+    word_with_role(input, WordRole::Adverb)
+}
@@ -0,0 +1,57 @@
+use super::{Change, ParseResult, Rule};
+use crate::{
+    behavior::{ops, Operation, Personality, Skill},
+    grammar::{word_with_def, AnnotatedPhrase, AnnotatedWord, WordFunction},
+};
+use nom::{branch::*, combinator::*, sequence::*};
+use rand::prelude::*;
+
+pub const CONJUNCTION: Skill = Skill::fluency("conjunction", 0.1);
+
+/// Joins two phrases with "and" to ask for both at once.
+pub struct Conjunction;
+impl Change for Conjunction {
+    fn skill(&self) -> Skill {
+        CONJUNCTION
+    }
+    /// Matches "A and B" or just "A"
+    /// TODO Move around the position of the conjunction.
+    fn parse<'a>(
+        &self,
+        input: &'a [AnnotatedWord],
+        lang: &Personality,
+        this: Rule<'_, 'a>,
+        inner: Rule<'_, 'a>,
+    ) -> ParseResult<'a> {
+        alt((
+            map(
+                separated_pair(
+                    inner,
+                    |i| word_with_def(i, WordFunction::And),
+                    // Allow recursion on conjunctions for X and (X and X), etc.
+                    this,
+                ),
+                |((a, a_l), (b, b_l))| {
+                    let op = if thread_rng().gen_bool(self.fluency(lang)) {
+                        Box::new(ops::Compound(a, b)) as Box<dyn Operation>
+                    } else {
+                        b
+                    };
+                    (op, a_l + b_l + self.exercise())
+                },
+            ),
+            inner,
+        ))(input)
+    }
+    /// The modifier is the conjunction followed by the second phrase, which
+    /// goes after the first.
+    fn attach(
+        &self,
+        _lang: &Personality,
+        mut head: AnnotatedPhrase,
+        modifier: AnnotatedPhrase,
+    ) -> AnnotatedPhrase {
+        head.extend(modifier);
+        head
+    }
+}
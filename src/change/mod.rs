@@ -1,3 +1,171 @@
 //! We want to provide additions and adaptations to the language as composable parts,
 //! ideally represented by one or two traits that capture how the change should influence
 //! speakers and listeners of the language.
+
+mod adverbs;
+mod conjunctions;
+mod numbers;
+mod prepositions;
+
+pub use adverbs::*;
+pub use conjunctions::*;
+pub use numbers::*;
+pub use prepositions::*;
+
+use crate::{
+    behavior::{ops::Relative, Language, Personality, Skill},
+    grammar::{self, AnnotatedPhrase, AnnotatedWord, Parsed},
+};
+use nom::{sequence::*, IResult};
+use rand::prelude::*;
+
+pub type ParseResult<'a> = IResult<&'a [AnnotatedWord], Parsed>;
+/// Parser for one layer of the grammar.
+pub type Rule<'r, 'a> = &'r dyn Fn(&'a [AnnotatedWord]) -> ParseResult<'a>;
+
+/// Whether objects come before their verb, as in "cheese want".
+pub const OBJECT_FIRST: Skill = Skill::word_order("object-first", 0.95);
+
+/// A single feature of the language, which contributes a grammar rule to the
+/// parser, a way of speaking that rule for encoders, and a skill that speakers
+/// improve by using it.
+pub trait Change: Sync {
+    /// The skill exercised every time a phrase uses this change.
+    fn skill(&self) -> Skill;
+    /// Any other skills this change relies on, like which side its words go on.
+    fn extra_skills(&self) -> &'static [Skill] {
+        &[]
+    }
+    /// One use of this change, for keeping score of what a phrase exercised.
+    fn exercise(&self) -> Language {
+        self.skill().into()
+    }
+    /// How likely the given speaker is to understand this change, from zero to one.
+    fn fluency(&self, lang: &Personality) -> f64 {
+        lang.skill(self.skill())
+    }
+    /// Matches a phrase modified by this change, or falls back to `inner`.
+    /// `this` matches another phrase at the same level, for changes that nest
+    /// or repeat, while `inner` only knows the changes below this one.
+    fn parse<'a>(
+        &self,
+        input: &'a [AnnotatedWord],
+        lang: &Personality,
+        this: Rule<'_, 'a>,
+        inner: Rule<'_, 'a>,
+    ) -> ParseResult<'a>;
+    /// Speak the modifier words of this change alongside the phrase they modify.
+    fn attach(
+        &self,
+        lang: &Personality,
+        head: AnnotatedPhrase,
+        modifier: AnnotatedPhrase,
+    ) -> AnnotatedPhrase;
+}
+
+/// Every change the language has picked up, from the outermost phrase inwards.
+pub static CHANGES: &[&dyn Change] = &[&Conjunction, &Numbers, &Adverbs, &AddAfter];
+
+/// Every skill the language can exercise.
+pub fn all_skills() -> impl Iterator<Item = Skill> {
+    CHANGES
+        .iter()
+        .flat_map(|c| std::iter::once(c.skill()).chain(c.extra_skills().iter().copied()))
+        .chain(std::iter::once(OBJECT_FIRST))
+}
+
+/// Matches a dependent and its head in whichever order the listener leans
+/// towards, noting which order they heard.
+pub fn ordered_pair<'a, D, H>(
+    input: &'a [AnnotatedWord],
+    lang: &Personality,
+    order: Skill,
+    dependent: impl Fn(&'a [AnnotatedWord]) -> IResult<&'a [AnnotatedWord], D>,
+    head: impl Fn(&'a [AnnotatedWord]) -> IResult<&'a [AnnotatedWord], H>,
+) -> IResult<&'a [AnnotatedWord], (D, H, Language)> {
+    if thread_rng().gen_bool(lang.skill(order)) {
+        let (rest, (d, h)) = pair(dependent, head)(input)?;
+        Ok((rest, (d, h, Language::new(order, 1))))
+    } else {
+        let (rest, (h, d)) = pair(head, dependent)(input)?;
+        Ok((rest, (d, h, Language::new(order, -1))))
+    }
+}
+
+/// Speak a dependent and its head in whichever order the speaker leans towards,
+/// the same way [ordered_pair] expects to hear them.
+pub fn ordered(
+    lang: &Personality,
+    order: Skill,
+    mut dependent: AnnotatedPhrase,
+    mut head: AnnotatedPhrase,
+) -> AnnotatedPhrase {
+    if thread_rng().gen_bool(lang.skill(order)) {
+        dependent.append(&mut head);
+        dependent
+    } else {
+        head.append(&mut dependent);
+        head
+    }
+}
+
+/// Matches a phrase using the given changes, all the way down to a bare clause.
+pub fn parse_with<'a>(
+    input: &'a [AnnotatedWord],
+    lang: &Personality,
+    changes: &[&dyn Change],
+) -> ParseResult<'a> {
+    match changes.split_first() {
+        Some((change, rest)) => {
+            change.parse(input, lang, &|i| parse_with(i, lang, changes), &|i| {
+                parse_with(i, lang, rest)
+            })
+        }
+        None => grammar::clause_new(input, &Relative::Top, lang),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        behavior::{ops::*, Operation},
+        grammar::sentence_new,
+    };
+    use itertools::Itertools;
+
+    fn say(lang: &Personality, op: &dyn Operation) -> String {
+        op.encode(lang).iter().map(|w| w.word.to_string()).join(" ")
+    }
+
+    /// Verbs and adpositions go on whichever side the speaker leans towards,
+    /// and a listener leaning the same way hears both the operation and the
+    /// order it came in.
+    #[test]
+    fn word_orders_round_trip() {
+        for &(order, heard) in &[(1.0, 1), (0.0, -1)] {
+            let lang = Personality::fluent(order);
+            let ingredient = |name| lang.dictionary.ingredients.from_def(name).unwrap().clone();
+            let (a, b) = (ingredient("avocado"), ingredient("bacon"));
+            let ops: Vec<Box<dyn Operation>> = vec![
+                Box::new(Add(a.clone(), Relative::Top)),
+                Box::new(Add(a.clone(), Relative::After(b.clone()))),
+                Box::new(Move(a.clone(), Relative::Between(b.clone(), a.clone()))),
+            ];
+            for op in ops {
+                let said = say(&lang, &*op);
+                let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+                assert_eq!(
+                    format!("{:?}", parsed.operation),
+                    format!("{:?}", op),
+                    "{}",
+                    said
+                );
+                assert_eq!(parsed.lang.get(OBJECT_FIRST), heard, "{}", said);
+            }
+            let said = say(&lang, &Add(a.clone(), Relative::Before(b.clone())));
+            let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+            assert_eq!(parsed.lang.get(POSTPOSITION), heard, "{}", said);
+        }
+    }
+}
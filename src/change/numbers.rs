@@ -0,0 +1,76 @@
+use super::{Change, ParseResult, Rule};
+use crate::{
+    behavior::{ops, Operation, Personality, Skill},
+    grammar::{word_with_def, AnnotatedPhrase, AnnotatedWord, WordFunction, NUMBER_BASE},
+};
+use nom::{branch::*, combinator::*, multi::*, sequence::*, IResult};
+use rand::prelude::*;
+
+pub const NUMBERS: Skill = Skill::fluency("numbers", 0.1);
+
+/// Number words in front of a phrase repeat it, "do A, X times".
+/// Bigger numbers take several words, see [number].
+pub struct Numbers;
+impl Change for Numbers {
+    fn skill(&self) -> Skill {
+        NUMBERS
+    }
+    /// Matches numbered phrases, either "do A, X times" or just "A".
+    fn parse<'a>(
+        &self,
+        input: &'a [AnnotatedWord],
+        lang: &Personality,
+        this: Rule<'_, 'a>,
+        inner: Rule<'_, 'a>,
+    ) -> ParseResult<'a> {
+        alt((
+            map(pair(number, this), |(n, (vp, l))| {
+                (
+                    if thread_rng().gen_bool(self.fluency(lang)) {
+                        Box::new(ops::Repeat(n, vp)) as Box<dyn Operation>
+                    } else {
+                        vp
+                    },
+                    l + self.exercise(),
+                )
+            }),
+            inner,
+        ))(input)
+    }
+    fn attach(
+        &self,
+        _lang: &Personality,
+        head: AnnotatedPhrase,
+        mut modifier: AnnotatedPhrase,
+    ) -> AnnotatedPhrase {
+        modifier.extend(head);
+        modifier
+    }
+}
+
+/// Matches a whole numeral, like "ko mo to" for 17, spelled as [Dictionary::numeral] does.
+///
+/// [Dictionary::numeral]: crate::grammar::Dictionary::numeral
+pub fn number(input: &[AnnotatedWord]) -> IResult<&[AnnotatedWord], u32> {
+    let hand = |i| verify(digit, |&d| d == NUMBER_BASE)(i);
+    let finger = |i| verify(digit, |&d| d < NUMBER_BASE)(i);
+    alt((
+        map(
+            pair(opt(finger), many1(pair(hand, opt(finger)))),
+            |(first, rest)| {
+                rest.into_iter().fold(first.unwrap_or(1), |n, (_, d)| {
+                    n * NUMBER_BASE + d.unwrap_or(0)
+                })
+            },
+        ),
+        finger,
+    ))(input)
+}
+
+/// Matches a single number word.
+fn digit(input: &[AnnotatedWord]) -> IResult<&[AnnotatedWord], u32> {
+    map_res(
+        |i| word_with_def(i, WordFunction::Number),
+        |w| w.entry.as_ref().unwrap().definition.parse::<u32>(),
+    )(input)
+}
@@ -1,8 +1,89 @@
+use super::{Change, ParseResult, Rule};
+use crate::{
+    behavior::{ops, Language, Personality, Skill},
+    grammar::{clause_new, ingredient, word_with_role, AnnotatedPhrase, AnnotatedWord, WordRole},
+};
+use nom::{branch::*, combinator::*, multi::*, sequence::*, IResult};
+use rand::prelude::*;
+
+pub const ADPOSITION: Skill = Skill::fluency("adposition", 0.1);
+/// Whether the noun comes before its adposition, as in "avocado after".
+pub const POSTPOSITION: Skill = Skill::word_order("postposition", 0.95);
+
 /// Adds a simple preposition to the language for adding one ingredient after another,
 /// specified by name.
 ///
 /// # Specification
 /// Basic Phrase: add cheese (cheese want)
 /// Modified Phrase: after avocado add cheese (avocado after cheese want)
-/// Upon hearing:
-pub struct AddAfter {}
+/// Upon hearing: put cheese right after the avocado already on the sandwich.
+///
+/// Other adpositions take two ingredients, like "between avocado and bacon",
+/// or none at all, like "at the bottom".
+pub struct AddAfter;
+impl Change for AddAfter {
+    fn skill(&self) -> Skill {
+        ADPOSITION
+    }
+    fn extra_skills(&self) -> &'static [Skill] {
+        &[POSTPOSITION]
+    }
+    fn parse<'a>(
+        &self,
+        input: &'a [AnnotatedWord],
+        lang: &Personality,
+        _this: Rule<'_, 'a>,
+        inner: Rule<'_, 'a>,
+    ) -> ParseResult<'a> {
+        if thread_rng().gen_bool(self.fluency(lang)) {
+            alt((
+                |i| {
+                    let (i, (rel, order)) = adposition(i, lang)?;
+                    let (i, (op, l)) = clause_new(i, &rel, lang)?;
+                    Ok((i, (op, l + order + self.exercise())))
+                },
+                inner,
+            ))(input)
+        } else {
+            // Skip over the adposition if we don't understand it.
+            preceded(opt(|i| adposition(i, lang)), inner)(input)
+        }
+    }
+    fn attach(
+        &self,
+        _lang: &Personality,
+        head: AnnotatedPhrase,
+        mut modifier: AnnotatedPhrase,
+    ) -> AnnotatedPhrase {
+        // Syntax is: V'[PP[NP P] V']
+        modifier.extend(head);
+        modifier
+    }
+}
+
+/// Matches an adposition along with however many ingredients it takes, from
+/// none for "at the bottom" to two for "between".
+fn adposition<'a>(
+    input: &'a [AnnotatedWord],
+    lang: &Personality,
+) -> IResult<&'a [AnnotatedWord], (ops::Relative, Language)> {
+    let preposition = |i| word_with_role(i, WordRole::Preposition);
+    let arity = |p: &AnnotatedWord| p.definition().map_or(0, |d| ops::Relative::arity(*d));
+    // Pick a word order based on our language system. We only know how many
+    // ingredients to expect once we've heard the adposition itself.
+    let (i, (anchors, p, order)) = if thread_rng().gen_bool(lang.skill(POSTPOSITION)) {
+        let (i, (anchors, p)) = pair(many_m_n(0, 2, |i| ingredient(i, lang)), preposition)(input)?;
+        (i, (anchors, p, Language::new(POSTPOSITION, 1)))
+    } else {
+        let (i, p) = preposition(input)?;
+        let (i, anchors) = count(|i| ingredient(i, lang), arity(p))(i)?;
+        (i, (anchors, p, Language::new(POSTPOSITION, -1)))
+    };
+    match p
+        .definition()
+        .and_then(|d| ops::Relative::from_def(*d, anchors))
+    {
+        Some(rel) => Ok((i, (rel, order))),
+        None => Err(nom::Err::Error((input, nom::error::ErrorKind::Count))),
+    }
+}
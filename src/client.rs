@@ -1,14 +1,17 @@
 use crate::{
     audio,
     behavior::{
-        ops, Behavior, DispatchMessage, Encoder, Event, Message, Operation, Order, Personality,
+        ops, Behavior, DispatchMessage, Encoder, EventKind, Meal, Message, Operation, Order,
+        Personality,
     },
+    change::{Change, Conjunction},
     comm,
     display::{PersonalityAction, Render},
     grammar,
     grammar::FullParse,
     sandwich::{Ingredient, Sandwich},
     state::{Idle, OrderingSandwich, State},
+    timeline::Timeline,
 };
 use async_std::future::timeout;
 use async_std::net::TcpStream;
@@ -23,10 +26,7 @@ use rand::prelude::*;
 // use futures::prelude::*;
 use futures::{pin_mut, select, FutureExt};
 use grammar::{sentence_new, Dictionary, PhraseNode};
-use std::{
-    thread,
-    time::{Duration, Instant},
-};
+use std::{thread, time::Duration};
 
 pub struct Client {
     /// We'll have a few words with default parts of speech if totally ambiguous.
@@ -64,15 +64,33 @@ impl Client {
             // Clear the display.
             self.lang.render(Render::clear())?;
 
+            // Stay dark and quiet while we're closed for the night.
+            if self.lang.asleep {
+                task::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+
             // Either be a client or server.
             let dur = Duration::from_millis(rng.gen_range(800, 2000));
             if rng.gen_bool(0.5) {
-                if let Ok(c) = timeout(dur, comm::find_peer()).await {
-                    dbg!(self.new_customer(c.0?, c.1).await);
+                // Running low on anything sends us out trading instead of ordering
+                // now and then, and any machine will do for that.
+                let trading = !self.lang.trade_wants().is_empty() && rng.gen_bool(0.5);
+                let lang = &self.lang;
+                if let Ok(c) =
+                    timeout(dur, comm::find_peer(|host| !trading && lang.shuns(host))).await
+                {
+                    if trading {
+                        dbg!(self.new_trader(c.0?, c.1).await);
+                    } else {
+                        dbg!(self.new_customer(c.0?, c.1, c.2).await);
+                    }
+                    self.lang.degrade_language_skills();
                 }
             } else {
                 if let Ok(c) = timeout(dur, comm::wait_for_peer()).await {
                     dbg!(self.new_server(c.0?, c.1).await);
+                    self.lang.degrade_language_skills();
                 }
             }
         }
@@ -85,16 +103,19 @@ impl Client {
         }
     }
 
-    async fn eat_sandwich(&mut self, sandwich: Sandwich) -> anyhow::Result<()> {
+    async fn eat_sandwich(&mut self, sandwich: Sandwich, desired: &Sandwich) -> anyhow::Result<()> {
         // Eat the sandwich ingredient by ingredient.
         // Alternate between background colors.
         let mut ingredients = sandwich.ingredients.clone();
+        let last = ingredients.len().saturating_sub(1);
         let mut color_alt = false;
+        let mut reaction = None;
         while !ingredients.is_empty() {
             self.lang.render(Render {
                 ingredients: Some(ingredients.clone()),
                 subtitles: Some(String::new()),
                 background: Some(if color_alt { "000000ff" } else { "ffffffff" }),
+                mood: None,
             })?;
 
             // Savor the sandwich!
@@ -106,15 +127,25 @@ impl Client {
             // If we're allergic to this ingredient, we might have a reaction.
             if let Some(top) = top {
                 if self.lang.allergic_reaction(&top) {
-                    self.have_seizure(top).await?;
-                    self.death_and_rebirth().await?;
+                    self.have_seizure(top.clone()).await?;
+                    self.death_and_rebirth(top.clone()).await?;
+                    reaction = Some(top);
                     break;
                 }
+                // Bread is bread, but what's inside shapes our tastes.
+                if !ingredients.is_empty() && ingredients.len() < last {
+                    self.lang.taste(&top, desired);
+                }
             }
         }
 
-        // Now eat the sandwich, and save in our history.
-        self.lang.eat(sandwich);
+        // Now eat the sandwich, and save in our history. After a reaction, that
+        // history belongs to our next life, who remembers what did us in.
+        self.lang.eat(Meal {
+            satisfied: sandwich.same_ingredients(desired),
+            sandwich,
+            reaction,
+        });
 
         // Make sure there's a delay between orders.
         task::sleep(Duration::from_millis(500)).await;
@@ -122,9 +153,10 @@ impl Client {
         Ok(())
     }
 
-    async fn death_and_rebirth(&mut self) -> anyhow::Result<()> {
+    async fn death_and_rebirth(&mut self, cause: Ingredient) -> anyhow::Result<()> {
         self.lang.render(Render::clear())?;
-        self.lang = Personality::new();
+        self.lang.reincarnate(&cause);
+        println!("Reborn as generation {}", self.lang.lineage.generation);
         task::sleep(Duration::from_millis(1500)).await;
         Ok(())
     }
@@ -135,6 +167,7 @@ impl Client {
             ingredients: Some(vec![allergen]),
             subtitles: None,
             background: None,
+            mood: None,
         })?;
         let flicker_gap = Duration::from_millis(100);
         let total_flickers: u32 = 2500 / 100;
@@ -146,6 +179,7 @@ impl Client {
                 ingredients: None,
                 subtitles: None,
                 background: Some(if color_alt { "000000ff" } else { "ffffffff" }),
+                mood: None,
             })?;
             task::sleep(flicker_gap).await;
         }
@@ -156,6 +190,7 @@ impl Client {
         &mut self,
         mut stream: TcpStream,
         color: &'static str,
+        host: &'static str,
     ) -> anyhow::Result<()> {
         let mut rng = thread_rng();
 
@@ -164,10 +199,13 @@ impl Client {
             ingredients: None,
             subtitles: None,
             background: Some(color),
+            mood: None,
         })?;
 
         // No greeting for now, treating the TCP connection itself as the greeting.
         let mut order = Order::new(&self.lang);
+        self.lang.undo = None;
+        self.lang.serving = false;
 
         println!("desired sandwich: {:?}", order.desired);
 
@@ -183,11 +221,7 @@ impl Client {
             let stress = self.lang.stress();
 
             // End any finished events.
-            if let Some(evt) = self.lang.event.as_ref() {
-                if evt.is_over() {
-                    self.lang.event = None;
-                }
-            }
+            self.lang.end_finished_event();
 
             // while let Ok(action) = self.lang.display.actions.try_recv() {
             //     action(&mut self.lang);
@@ -207,14 +241,18 @@ impl Client {
             // next operation. Or start waiting if there's a buffer of
             // messages that haven't been acknowledged.
             let min_wait = (200.0 * self.lang.shyness * 10.0 / stress) as u64;
-            let wait_time = Duration::from_millis(rng.gen_range(
-                min_wait,
-                (800.0 * self.lang.politeness * 10.0 / stress) as u64,
-            ));
+            // Events can leave us shy enough and rude enough that we'd rather
+            // wait longer than we have patience for, so at least wait that.
+            let max_wait =
+                ((800.0 * self.lang.politeness * 10.0 / stress) as u64).max(min_wait + 1);
+            let wait_time = Duration::from_millis(rng.gen_range(min_wait, max_wait));
             task::sleep(wait_time).await;
             while let Ok(msg) = msg_rx.try_next() {
                 if let Some(msg) = msg {
                     // We have received a message!
+                    if let Some(notice) = &msg.event {
+                        self.lang.hear_event(notice);
+                    }
                     if let Some(sandwich) = msg.sandwich {
                         println!("received {}", sandwich);
                         self.last_result = sandwich;
@@ -226,9 +264,36 @@ impl Client {
                         msg.text.and_then(|t| self.parse(&t))
                     {
                         println!("Received response op: {:?}", operation);
+                        // A "no" answers our question, or otherwise means they
+                        // couldn't understand or do what we asked.
+                        order.heard_no = operation.is_negation();
+                        if order.heard_no && !order.last_op().map_or(false, |op| op.is_question()) {
+                            failed_attempts += 1;
+                        }
                         order.desired = operation.apply(order.desired.clone(), &mut self.lang);
                         self.lang.last_lex = Some(lex);
 
+                        // Anything they'll never put on, out of what we want
+                        // or love, must be sold out here.
+                        if operation.is_persistent() {
+                            let wanted = Sandwich::new(
+                                order
+                                    .desired
+                                    .ingredients
+                                    .iter()
+                                    .chain(self.lang.preferences.iter().map(|p| &p.ingredient))
+                                    .cloned()
+                                    .collect(),
+                            );
+                            let left = operation.apply(wanted.clone(), &mut self.lang);
+                            for x in &wanted.ingredients {
+                                if !left.ingredients.contains(x) {
+                                    println!("{} is out of {}", host, x.name);
+                                    self.lang.hear_sold_out(host, x);
+                                }
+                            }
+                        }
+
                         // If we asked a question that caused a change in our
                         // sandwich, affirm that we understood it.
                         if order.last_question_failed(&mut self.lang, &self.last_result) {
@@ -252,6 +317,11 @@ impl Client {
                     .await?;
             } else {
                 failed_attempts += 1;
+                // Tell them if they did something we didn't ask for.
+                if order.last_op_mistaken(&mut self.lang, &self.last_result) {
+                    self.say_and_send(&mut stream, Some(&ops::NegateLast), None)
+                        .await?;
+                }
             }
 
             // Send over the next operation!
@@ -260,14 +330,18 @@ impl Client {
             if let Some(mut op) = op {
                 // Request two operations at once if planned and not shy.
                 if rng.gen_bool((self.lang.planned * stress).min(0.95))
-                    && !rng.gen_bool(self.lang.shyness / stress)
-                    && rng.gen_bool(self.lang.conjunction)
+                    && !rng.gen_bool((self.lang.shyness / stress).min(0.99))
+                    && rng.gen_bool(Conjunction.fluency(&self.lang))
                 {
                     let assumed_sandwich = op.apply(self.last_result.clone(), &mut self.lang);
                     if let Some(next_op) = order.pick_op(&self.lang, &assumed_sandwich) {
                         op = Box::new(ops::Compound(op, next_op));
                     }
                 }
+                // Polite machines ask nicely.
+                if rng.gen_bool(self.lang.politeness) {
+                    op = Box::new(ops::Polite(op));
+                }
                 println!("op: {:?}", op);
                 self.say_and_send(&mut stream, Some(&*op), None).await?;
                 // Send this operation to our history box.
@@ -279,14 +353,106 @@ impl Client {
             }
         }
         // Say thank you and goodbye.
-        self.say_and_send(&mut stream, Some(&ops::Finish), None)
+        let goodbye: Box<dyn Operation> = if rng.gen_bool(self.lang.politeness) {
+            Box::new(ops::Polite(Box::new(ops::Finish)))
+        } else {
+            Box::new(ops::Finish)
+        };
+        self.say_and_send(&mut stream, Some(&*goodbye), None)
             .await?;
+
+        // Wait for the bill, looking past anything else they mention, like a
+        // late answer to something we said before.
+        while let Ok(Some(msg)) = timeout(Duration::from_secs(10), msg_rx.next()).await {
+            let operation = match msg.text.and_then(|t| self.parse(&t)) {
+                Some(FullParse { operation, .. }) if operation.is_payment() => operation,
+                _ => continue,
+            };
+            // Pay whatever we can of it, or refuse if we're broke.
+            let before = self.lang.wallet;
+            operation.apply(Sandwich::default(), &mut self.lang);
+            let paid = before - self.lang.wallet;
+            println!("Paid {} of the bill, {} left", paid, self.lang.wallet);
+            let reply: Box<dyn Operation> = if paid > 0 {
+                Box::new(ops::Pay(paid))
+            } else {
+                Box::new(ops::Negate)
+            };
+            self.say_and_send(&mut stream, Some(&*reply), None).await?;
+            break;
+        }
         if let Some(sandwich) = order.last_result {
-            self.eat_sandwich(sandwich).await?;
+            self.lang.got_from(host, &sandwich);
+            self.eat_sandwich(sandwich, &order.desired).await?;
         }
         Ok(())
     }
 
+    /// Ask another machine for what we're short on, paying them back out of
+    /// whatever we have the most of.
+    async fn new_trader(
+        &mut self,
+        mut stream: TcpStream,
+        color: &'static str,
+    ) -> anyhow::Result<()> {
+        self.lang.render(Render {
+            ingredients: None,
+            subtitles: None,
+            background: Some(color),
+            mood: None,
+        })?;
+        self.lang.undo = None;
+        self.lang.serving = false;
+
+        // Stock only changes hands on our side once they've said it did on theirs.
+        for want in self.lang.trade_wants().into_iter().take(2) {
+            let take = ops::Take(want);
+            self.say_and_send(&mut stream, Some(&take), None).await?;
+            if !self.hear_yes(&mut stream).await? {
+                continue;
+            }
+            take.reverse().apply(Sandwich::default(), &mut self.lang);
+
+            if let Some(offer) = self.lang.surplus() {
+                let give = ops::Give(offer);
+                self.say_and_send(&mut stream, Some(&give), None).await?;
+                if self.hear_yes(&mut stream).await? {
+                    give.reverse().apply(Sandwich::default(), &mut self.lang);
+                }
+            }
+        }
+        self.say_and_send(&mut stream, Some(&ops::Finish), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Waits for the other machine to answer us, looking past anything else
+    /// they mention in the meantime, like what they're out of. Anything but a
+    /// plain "yes" means they didn't go along with it.
+    async fn hear_yes(&mut self, stream: &mut TcpStream) -> anyhow::Result<bool> {
+        loop {
+            let msg = timeout(Duration::from_secs(20), Message::recv(stream)).await??;
+            if let Some(notice) = &msg.event {
+                self.lang.hear_event(notice);
+            }
+            match msg.text.and_then(|t| self.parse(&t)) {
+                Some(FullParse { operation, .. }) if operation.is_persistent() => continue,
+                Some(FullParse { operation, .. }) => return Ok(operation.is_affirmation()),
+                None => return Ok(false),
+            }
+        }
+    }
+
+    /// Tell our customer we won't be putting this on anything.
+    async fn announce_sold_out(
+        &self,
+        stream: &mut TcpStream,
+        ingredient: &Ingredient,
+    ) -> anyhow::Result<()> {
+        let never = ops::Persist(Box::new(ops::Remove(ingredient.clone())));
+        self.say_and_send(stream, Some(&never), None).await
+    }
+
     async fn say_and_send(
         &self,
         stream: &mut TcpStream,
@@ -297,7 +463,8 @@ impl Client {
         let phrase = op.map(|op| op.encode(&self.lang));
         let s = phrase.map(|phrase| phrase.into_iter().map(|x| x.word.to_string()).join(" "));
         self.say_phrase(s.as_deref(), sandwich.clone()).await?;
-        let message = Message::new(s.to_owned(), sandwich);
+        let mut message = Message::new(s.to_owned(), sandwich);
+        message.event = self.lang.event.as_ref().and_then(|e| e.passed_on());
         dbg!(&message);
         message.send(stream).await?;
         Ok(())
@@ -308,6 +475,11 @@ impl Client {
         println!("running central dispatch");
         // Connect to all sandwich machines.
         let mut connections = comm::central_dispatch().await;
+        // A timeline runs the show on its own, otherwise it's up to whoever's
+        // at the keyboard.
+        if let Some(timeline) = Timeline::load()? {
+            return timeline.run(&mut connections).await;
+        }
         // Then accept real-time events from the window...
         while let Ok(key) = self.lang.display.keys.recv() {
             match key {
@@ -318,6 +490,17 @@ impl Client {
                 Button::Keyboard(Key::D5) => exclusive_host = Some(&comm::HOSTS[4]),
                 Button::Keyboard(Key::D6) => exclusive_host = Some(&comm::HOSTS[5]),
                 Button::Keyboard(Key::D0) => exclusive_host = None,
+                // Events start at the chosen machine and ripple out from there,
+                // or hit the whole room at once if none is chosen.
+                Button::Keyboard(key) if EventKind::for_key(key).is_some() => {
+                    let name = EventKind::for_key(key).unwrap();
+                    for (host, notice) in comm::event_wave(name, exclusive_host.copied()) {
+                        if let Some(stream) = connections.get_mut(host) {
+                            println!("sending {:?} to {}", notice, host);
+                            DispatchMessage::Event(notice).send(stream).await?;
+                        }
+                    }
+                }
                 _ => {
                     // ...and dispatch them.
                     // For now, all key codes to all clients.
@@ -325,7 +508,7 @@ impl Client {
                         let matches = exclusive_host.map(|h| host == h).unwrap_or(true);
                         if matches {
                             println!("sending {:?} to {}", key, host);
-                            DispatchMessage::new(key).send(stream).await?;
+                            DispatchMessage::Key(key).send(stream).await?;
                         }
                     }
                 }
@@ -341,27 +524,43 @@ impl Client {
             .expect("Couldn't connect to central dispatch");
         task::spawn(async move {
             loop {
-                if let Ok(msg) = DispatchMessage::recv(&mut connection).await {
-                    match msg.key {
-                        Button::Keyboard(Key::A) => {
-                            sx.send(|p| {
-                                println!("AVOCADO!!");
-                                p.increase_preference("avocado")
-                            })
+                match DispatchMessage::recv(&mut connection).await {
+                    Ok(DispatchMessage::Key(Button::Keyboard(Key::A))) => {
+                        sx.send(Box::new(|p| {
+                            println!("AVOCADO!!");
+                            p.increase_preference("avocado")
+                        }))
+                        .await;
+                    }
+                    Ok(DispatchMessage::Key(Button::Keyboard(Key::E))) => {
+                        sx.send(Box::new(|p| p.increase_preference("fried-egg")))
+                            .await;
+                    }
+                    Ok(DispatchMessage::Key(Button::Keyboard(Key::S))) => {
+                        sx.send(Box::new(|p| p.spite += 0.1)).await;
+                    }
+                    Ok(DispatchMessage::Prefer(name)) => {
+                        sx.send(Box::new(move |p| p.increase_preference(&name)))
                             .await;
-                        }
-                        Button::Keyboard(Key::E) => {
-                            sx.send(|p| p.increase_preference("fried-egg")).await;
-                        }
-                        Button::Keyboard(Key::S) => {
-                            sx.send(|p| p.spite += 0.1).await;
-                        }
-                        Button::Keyboard(Key::R) => {
-                            sx.send(|p| p.event = Some(Event::LunchRush(Instant::now())))
-                                .await;
-                        }
-                        _ => {}
                     }
+                    Ok(DispatchMessage::Restock) => {
+                        sx.send(Box::new(|p| p.restock())).await;
+                    }
+                    Ok(DispatchMessage::Sleep) => {
+                        sx.send(Box::new(|p| p.asleep = true)).await;
+                    }
+                    Ok(DispatchMessage::Wake) => {
+                        sx.send(Box::new(|p| p.asleep = false)).await;
+                    }
+                    Ok(DispatchMessage::Event(notice)) => {
+                        // Let the wave reach us in its own time.
+                        let mut sx = sx.clone();
+                        task::spawn(async move {
+                            task::sleep(Duration::from_millis(notice.delay_ms)).await;
+                            sx.send(Box::new(move |p| p.hear_event(&notice))).await
+                        });
+                    }
+                    _ => {}
                 }
             }
         });
@@ -373,35 +572,40 @@ impl Client {
         mut stream: TcpStream,
         color: &'static str,
     ) -> anyhow::Result<()> {
-        let mut rng = thread_rng();
+        self.lang.serving = true;
 
-        // Refill the ingredient inventory when we get really low on
-        // *everything*. So we could run out of several things before
-        // hitting the reset.
-        if self.lang.total_inventory_count() < 10 {
-            self.lang.reset_inventory();
-        }
+        // Deliveries come every few orders.
+        self.lang.start_shift();
 
         // Set the shared background color.
         self.lang.render(Render {
             ingredients: None,
             subtitles: None,
             background: Some(color),
+            mood: None,
         })?;
 
         let mut order = Order::new(&self.lang);
         self.last_result = Sandwich::default();
+        // Remember what they've turned down and how often they've corrected
+        // us in a row, which both feed our spite.
+        let mut disliked = Vec::new();
+        let mut corrections = 0;
+
+        // Let them know up front about a couple of things we're out of.
+        let mut announced = Vec::new();
+        let sold_out = self.lang.sold_out_items();
+        for x in sold_out.choose_multiple(&mut thread_rng(), 2) {
+            self.announce_sold_out(&mut stream, x).await?;
+            announced.push(x.clone());
+        }
         // Only break the loop when the order is complete.
         while !self.last_result.complete {
             // Stress modifier multiplies value intesities, shortens wait times, etc.
             let stress = self.lang.stress();
 
             // End any finished events.
-            if let Some(evt) = self.lang.event.as_ref() {
-                if evt.is_over() {
-                    self.lang.event = None;
-                }
-            }
+            self.lang.end_finished_event();
 
             // If there's been user interaction, make sure to apply the results.
             // while let Ok(Some(action)) = actions.try_next() {
@@ -413,6 +617,9 @@ impl Client {
 
             // TODO This machine might wait to receive multiple operations before applying them all at once.
             let msg = timeout(Duration::from_secs(20), Message::recv(&mut stream)).await??;
+            if let Some(notice) = &msg.event {
+                self.lang.hear_event(notice);
+            }
 
             // If there are zeroes, we might parse as a (None, None) accidentally.
             // So let's check for that.
@@ -421,28 +628,68 @@ impl Client {
                 // break;
             }
 
+            let text = msg.text;
             if let Some(FullParse {
                 operation: mut op,
                 lang: lang_change,
                 lex,
-            }) = msg.text.and_then(|t| self.parse(&t))
+                polite,
+            }) = text.as_ref().and_then(|t| self.parse(t))
             {
+                // Trades don't touch the sandwich, so there's nothing to take
+                // out on them. Just say plainly whether the stock changed hands.
+                if op.is_trade() {
+                    let agreed = op.holds(&self.last_result, &self.lang, 1);
+                    let resp: Box<dyn Operation> = if agreed {
+                        op.apply(self.last_result.clone(), &mut self.lang);
+                        Box::new(ops::Affirm)
+                    } else {
+                        Box::new(ops::Negate)
+                    };
+                    self.say_and_send(&mut stream, Some(&*resp), None).await?;
+                    continue;
+                }
+
                 // Apply all persistent operations at every turn.
                 for passive_op in &order.persistent_ops {
                     self.last_result = passive_op.apply(self.last_result.clone(), &mut self.lang);
                 }
 
                 // If spite is high enough, do the opposite of their order.
-                if rng.gen_bool((self.lang.spite * stress).min(0.99)) {
+                if self.lang.spite_reversal(stress) {
                     op = op.reverse();
-                    // Feel the release of anger calm you.
-                    self.lang.spite = 0.0;
                 }
 
-                // Apply the operation to our sandwich.
+                // Apply the operation to our sandwich, remembering how to take
+                // it back if they say "no".
+                let before = self.last_result.clone();
                 self.last_result = op.apply(self.last_result.clone(), &mut self.lang);
+                let changed = !self.last_result.same_layers(&before);
+                if changed {
+                    self.lang.undo = Some(op.undo(&before));
+                }
+
+                // Rearranging what we already made, or saying "no", corrects us.
+                let corrected = op.is_negation()
+                    || (changed && self.last_result.ingredients.len() == before.ingredients.len());
+                corrections = if corrected { corrections + 1 } else { 0 };
+                disliked.extend(
+                    before
+                        .ingredients
+                        .iter()
+                        .filter(|x| !self.last_result.ingredients.contains(x))
+                        .cloned(),
+                );
+                self.lang
+                    .hear_request(polite, &before, &self.last_result, corrections);
+                self.last_result =
+                    self.lang
+                        .act_out(&before, self.last_result.clone(), &disliked, stress);
+
                 self.lang.apply_upgrade(lang_change);
-                let resp = op.respond(&self.lang);
+                let resp = op.respond(&self.last_result, &self.lang);
+                // Take our sweet time answering when we're fed up.
+                task::sleep(self.lang.spite_delay()).await;
                 self.say_and_send(
                     &mut stream,
                     resp.as_ref().map(|x| &**x),
@@ -454,6 +701,14 @@ impl Client {
                     order.persistent_ops.push(op);
                 }
 
+                // Tell them right away if that was the last of something.
+                for x in self.lang.sold_out_items() {
+                    if !announced.contains(&x) {
+                        self.announce_sold_out(&mut stream, &x).await?;
+                        announced.push(x);
+                    }
+                }
+
                 // Save the lex of this phrase for one turn.
                 // If we receive a positive reply from the client machine, use
                 // this lex to update our word association weights.
@@ -461,12 +716,37 @@ impl Client {
                 // TODO Check if `op` is an affirmation, in which case use last_lex!
                 println!("lexed {:?}", lex);
                 self.lang.last_lex = Some(lex);
-            } else {
-                println!("Failed to parse phrase")
+            } else if text.is_some() {
+                println!("Failed to parse phrase");
+                // Let them know we didn't understand.
+                self.say_and_send(&mut stream, Some(&ops::Negate), None)
+                    .await?;
             }
         }
 
         println!("The order is finished!");
+
+        // Hand them the bill, and see how much of it they pay.
+        let bill = self.lang.price_of(&self.last_result);
+        if bill > 0 {
+            self.say_and_send(&mut stream, Some(&ops::Pay(bill)), None)
+                .await?;
+            let before = self.lang.wallet;
+            if let Ok(Ok(msg)) = timeout(Duration::from_secs(10), Message::recv(&mut stream)).await
+            {
+                if let Some(FullParse { operation, .. }) = msg.text.and_then(|t| self.parse(&t)) {
+                    if operation.is_payment() {
+                        operation.apply(Sandwich::default(), &mut self.lang);
+                    }
+                }
+            }
+            let paid = self.lang.wallet - before;
+            println!(
+                "Got paid {} of {}, now have {}",
+                paid, bill, self.lang.wallet
+            );
+            self.lang.hear_payment(bill, paid);
+        }
         Ok(())
     }
 
@@ -500,6 +780,7 @@ impl Client {
                     .unwrap_or(String::new()),
             ),
             background: None,
+            mood: Some(self.lang.spite),
         })?;
 
         // Play the phrase out loud.
@@ -1,4 +1,4 @@
-use crate::behavior::{self, Event, Operation};
+use crate::behavior::{self, Event, EventNotice, Operation};
 use async_std::io;
 use async_std::net::{TcpListener, TcpStream};
 use hostname;
@@ -38,14 +38,18 @@ pub fn is_dispatch_host() -> bool {
     our_name == DISPATCH_HOST
 }
 
-pub async fn find_peer() -> (std::io::Result<TcpStream>, &'static str) {
+/// Connects to a random peer, passing over any that `shun` says we'd rather
+/// not visit. Returns the stream, their background color, and their hostname.
+pub async fn find_peer(
+    shun: impl Fn(&str) -> bool,
+) -> (std::io::Result<TcpStream>, &'static str, &'static str) {
     // Try connecting to a random peer until it succeeds.
     let ourselves = hostname::get().expect("We should have a hostname");
     let our_name = ourselves.as_os_str().to_str().unwrap();
     println!("our hostname = {}", our_name);
     loop {
         let host = HOSTS.choose(&mut thread_rng()).unwrap();
-        if host.eq_ignore_ascii_case(our_name) {
+        if host.eq_ignore_ascii_case(our_name) || shun(host) {
             continue;
         }
 
@@ -54,7 +58,7 @@ pub async fn find_peer() -> (std::io::Result<TcpStream>, &'static str) {
         let stream = io::timeout(Duration::from_millis(300), TcpStream::connect(url)).await;
         if stream.is_ok() {
             println!("Connected to {}", host);
-            return (stream, BG_COLORS[host]);
+            return (stream, BG_COLORS[host], host);
         }
     }
 }
@@ -77,6 +81,20 @@ pub async fn wait_for_central_dispatch() -> std::io::Result<TcpStream> {
     Ok(stream)
 }
 
+/// Word of an event for every machine, spreading out from `origin` one
+/// machine at a time, or reaching the whole room at once without one.
+pub fn event_wave(name: &str, origin: Option<&str>) -> Vec<(&'static str, EventNotice)> {
+    let origin = origin.and_then(|o| HOSTS.iter().position(|h| *h == o));
+    HOSTS
+        .iter()
+        .enumerate()
+        .map(|(idx, host)| {
+            let distance = origin.map_or(0, |o| (idx as i32 - o as i32).unsigned_abs());
+            (*host, EventNotice::at_distance(name, distance))
+        })
+        .collect()
+}
+
 // Returns a map of hostname to the relevant TCP stream.
 pub async fn central_dispatch() -> HashMap<&'static str, TcpStream> {
     let ourselves = hostname::get().expect("We should have a hostname");
@@ -100,9 +118,3 @@ pub async fn central_dispatch() -> HashMap<&'static str, TcpStream> {
     }
     result
 }
-
-// #[derive(Serialize, Deserialize)]
-// pub enum DispatchMessage {
-//     Op(Box<dyn Operation>),
-//     Event(Event),
-// }
@@ -12,6 +12,8 @@ pub struct Render {
     pub ingredients: Option<Vec<Ingredient>>,
     pub subtitles: Option<String>,
     pub background: Option<&'static str>,
+    /// How fed up we are, from zero to one, which tints what we say red.
+    pub mood: Option<f64>,
 }
 impl Render {
     pub fn clear() -> Self {
@@ -19,6 +21,7 @@ impl Render {
             ingredients: Some(Default::default()),
             subtitles: Some(Default::default()),
             background: Some("000000ff"),
+            mood: Some(0.0),
         }
     }
 }
@@ -29,7 +32,21 @@ pub struct Display {
     pub keys: Receiver<Button>,
 }
 
-pub type PersonalityAction = fn(&mut Personality) -> ();
+impl Display {
+    /// A display without any window attached, whose renders go nowhere.
+    pub fn headless() -> Self {
+        let (render, _) = sync_channel(1);
+        let (_, actions) = sync_channel(1);
+        let (_, keys) = sync_channel(1);
+        Self {
+            render,
+            actions,
+            keys,
+        }
+    }
+}
+
+pub type PersonalityAction = Box<dyn FnOnce(&mut Personality) + Send>;
 pub type RenderSender = SyncSender<Render>;
 
 // TODO Render both ingredients and subtitles.
@@ -62,8 +79,10 @@ pub fn setup_display<'a>() -> Display {
             let mut texture_map = HashMap::new();
             let mut textures = Vec::new();
             let mut rotations = Vec::<f64>::new();
+            let mut sizes = Vec::<f64>::new();
             let mut subtitles = String::new();
             let mut background = [0.0, 0.0, 0.0, 1.0];
+            let mut mood = 0.0;
             while let Some(e) = events.next(&mut window) {
                 // Try to receive render updates if there are any.
                 if let Ok(render) = receiver.try_recv() {
@@ -77,6 +96,11 @@ pub fn setup_display<'a>() -> Display {
                             }
                         }
 
+                        // Bigger portions look a bit bigger, but not as big as they are.
+                        sizes = ingr
+                            .iter()
+                            .map(|x| 1.0 + (x.portion.amount() - 1.0) * 0.4)
+                            .collect();
                         textures = ingr
                             .into_iter()
                             .map(|x| {
@@ -104,13 +128,17 @@ pub fn setup_display<'a>() -> Display {
                     if let Some(bg) = render.background {
                         background = piston_window::color::hex(bg);
                     }
+                    if let Some(m) = render.mood {
+                        mood = m as f32;
+                    }
                 }
                 window.draw_2d(&e, |c, g, d| {
                     clear(background, g);
 
                     // Render the subtitles.
                     let sub_t = c.transform.trans(200.0, 900.0);
-                    text([1.0, 1.0, 1.0, 1.0], 40, &subtitles, &mut font, sub_t, g).unwrap();
+                    let calm = 1.0 - mood;
+                    text([1.0, calm, calm, 1.0], 40, &subtitles, &mut font, sub_t, g).unwrap();
                     // Push all text to the screen.
                     font.factory.encoder.flush(d);
 
@@ -125,7 +153,7 @@ pub fn setup_display<'a>() -> Display {
                             .transform
                             .trans(960.0, 600.0 - offset * idx as f64)
                             .rot_deg(rot)
-                            .scale(scale, scale)
+                            .scale(scale * sizes[idx], scale * sizes[idx])
                             // Anchor elements at theit center point.
                             .trans(-16.0, -16.0);
                         image(t, transform, g);
@@ -1,5 +1,9 @@
 use crate::behavior::{ops, Language, Operation};
-use crate::{behavior::personality::Personality, sandwich::Ingredient};
+use crate::change::{self, Change};
+use crate::{
+    behavior::personality::Personality,
+    sandwich::{Ingredient, Portion, Preparation},
+};
 use itertools::Itertools;
 use lazy_static::*;
 use nom::{branch::*, bytes::complete::*, combinator::*, multi::*, sequence::*, IResult, *};
@@ -27,6 +31,9 @@ lazy_static! {
     };
 }
 
+/// Numbers past this one are built out of several number words.
+pub const NUMBER_BASE: u32 = 5;
+
 pub struct Dictionary {
     words: HashMap<String, DictionaryEntry>,
     pub ingredients: Ingredient,
@@ -61,18 +68,65 @@ impl Dictionary {
         }
         unreachable!("There should be at least one word per function.")
     }
-    pub fn annotated_word_for_num(&self, number: u32) -> AnnotatedWord {
-        let w = self.word_for_num(number);
-        AnnotatedWord::from_dict(w.0, w.1)
-    }
-    pub fn word_for_num(&self, number: u32) -> (&str, &DictionaryEntry) {
-        let num_str = number.to_string();
-        for (word, entry) in &self.words {
-            if entry.function == WordFunction::Number && entry.definition == num_str {
-                return (word, entry);
+    /// Spells out a number in base five, the way one counts on a hand: each
+    /// "five" multiplies everything said so far, and a digit word adds to it.
+    /// A lone "five" stands for one hand, so 5 is "mo", 7 is "mo to" and 27 is
+    /// "mo mo to".
+    pub fn numeral(&self, number: u32) -> AnnotatedPhrase {
+        let mut digits = Vec::new();
+        let mut n = number;
+        while n > 0 {
+            digits.push(n % NUMBER_BASE);
+            n /= NUMBER_BASE;
+        }
+        let mut phrase = AnnotatedPhrase::new();
+        for (place, digit) in digits.into_iter().rev().enumerate() {
+            if place > 0 {
+                phrase.push(self.annotated_word_for_num(NUMBER_BASE));
+            }
+            // The leading one is implied by the "five" after it.
+            if digit > 0 && !(place == 0 && digit == 1 && number >= NUMBER_BASE) {
+                phrase.push(self.annotated_word_for_num(digit));
             }
         }
-        todo!("There is no word for number {}", number)
+        phrase
+    }
+    fn annotated_word_for_num(&self, number: u32) -> AnnotatedWord {
+        self.annotated_word_for(WordFunction::Number, &number.to_string())
+            .unwrap_or_else(|| {
+                unreachable!(
+                    "There should be a word for every digit up to {}.",
+                    NUMBER_BASE
+                )
+            })
+    }
+    /// Speaks an ingredient along with how much of it we mean and how it's
+    /// cooked, like "extra melted cheese".
+    pub fn noun_phrase(&self, ingredient: &Ingredient) -> AnnotatedPhrase {
+        let mut phrase = AnnotatedPhrase::new();
+        if ingredient.portion != Portion::Normal {
+            phrase.extend(
+                self.annotated_word_for(WordFunction::Portion, ingredient.portion.definition()),
+            );
+        }
+        if ingredient.preparation != Preparation::Plain {
+            phrase.extend(self.annotated_word_for(
+                WordFunction::Preparation,
+                ingredient.preparation.definition(),
+            ));
+        }
+        phrase.push(self.ingredients.to_annotated_word(ingredient));
+        phrase
+    }
+    fn annotated_word_for(
+        &self,
+        function: WordFunction,
+        definition: &str,
+    ) -> Option<AnnotatedWord> {
+        self.words
+            .iter()
+            .find(|(_, entry)| entry.function == function && entry.definition == definition)
+            .map(|(word, entry)| AnnotatedWord::from_dict(word, entry))
     }
     pub fn get(&self, word: &str) -> Option<&DictionaryEntry> {
         self.words.get(word)
@@ -99,8 +153,26 @@ pub enum WordFunction {
     Action,
     Desire,
     Have,
+    Swap,
+    Replace,
+    Move,
+    /// To make an ingredient a different portion.
+    Make,
+    /// To cook an ingredient some way.
+    Prepare,
+    /// To hand some stock over to another machine.
+    Give,
+    /// To ask another machine for some of their stock.
+    Take,
+    /// To hand over money, or ask for it.
+    Pay,
     After,
     Before,
+    Between,
+    Bottom,
+    OnTopOfBase,
+    /// Marks what something gets replaced with.
+    With,
     And,
     Ever,
     /// Please and thank you.
@@ -108,6 +180,10 @@ pub enum WordFunction {
     // Lexical Functions
     Sandwich,
     Number,
+    /// How much of an ingredient, like light or extra.
+    Portion,
+    /// How an ingredient is cooked, like toasted or melted.
+    Preparation,
     /// Has some meaning beyond function.
     Ingredient,
 }
@@ -132,7 +208,7 @@ pub enum WordRole {
     NounConjunction,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct DictionaryEntry {
     pub function: WordFunction,
     pub role: WordRole,
@@ -140,7 +216,7 @@ pub struct DictionaryEntry {
 }
 
 /// Syllables are always two characters, CV.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Syllable(char, char);
 impl Display for Syllable {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -148,7 +224,7 @@ impl Display for Syllable {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Word(pub Vec<Syllable>);
 impl Display for Word {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -161,7 +237,7 @@ impl Display for Word {
 pub type Phrase = Vec<Word>;
 pub type AnnotatedPhrase = Vec<AnnotatedWord>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AnnotatedWord {
     // TODO: Syllables -> Morphemes
     pub word: Word,
@@ -233,16 +309,17 @@ pub fn annotate(phrase: Phrase, context: &Personality) -> AnnotatedPhrase {
 pub fn sentence_new(input: &[u8], lang: &Personality) -> Option<FullParse> {
     phrase(input).ok().and_then(|(_, parsed)| {
         let tagged = annotate(parsed, lang);
-        if let Ok((_, op)) = sentence(&tagged, lang) {
+        let (plain, polite) = without_politeness(&tagged);
+        if let Ok((_, op)) = sentence(&plain, lang) {
             Some(FullParse {
                 operation: op.0,
                 lex: tagged,
                 lang: op.1,
+                polite,
             })
         } else {
             // Try again with unknown words removed.
-            let nt = tagged
-                .clone()
+            let nt = plain
                 .into_iter()
                 .filter(|x| x.entry.as_ref().map(|x| x.role).is_some() || x.entry.is_some())
                 .collect_vec();
@@ -251,11 +328,23 @@ pub fn sentence_new(input: &[u8], lang: &Personality) -> Option<FullParse> {
                 operation: t.0,
                 lang: t.1,
                 lex: tagged,
+                polite,
             })
         }
     })
 }
 
+/// "Please" can go anywhere in a sentence, so pull it out before parsing the
+/// rest, and note whether we heard it.
+fn without_politeness(tagged: &[AnnotatedWord]) -> (AnnotatedPhrase, bool) {
+    let (polite, plain): (Vec<_>, Vec<_>) = tagged.iter().cloned().partition(|w| {
+        w.entry
+            .as_ref()
+            .is_some_and(|e| e.function == WordFunction::Polite)
+    });
+    (plain, !polite.is_empty())
+}
+
 #[derive(Debug)]
 pub enum PhraseNode {
     NounPhrase(Vec<PhraseNode>),
@@ -378,23 +467,35 @@ pub fn noun(input: &[AnnotatedWord]) -> IResult<&[AnnotatedWord], PhraseNode> {
     }
 }
 
-fn ingredient<'a>(
+/// NP -> (Portion) (Preparation) N
+pub fn ingredient<'a>(
     input: &'a [AnnotatedWord],
     lang: &Personality,
 ) -> IResult<&'a [AnnotatedWord], Ingredient> {
     map(
-        |i| word_with_def(i, WordFunction::Ingredient),
-        |w| {
+        tuple((
+            opt(map_opt(
+                |i| word_with_def(i, WordFunction::Portion),
+                |w| Portion::from_def(&w.entry.as_ref().unwrap().definition),
+            )),
+            opt(map_opt(
+                |i| word_with_def(i, WordFunction::Preparation),
+                |w| Preparation::from_def(&w.entry.as_ref().unwrap().definition),
+            )),
+            |i| word_with_def(i, WordFunction::Ingredient),
+        )),
+        |(portion, preparation, w)| {
             lang.dictionary
                 .ingredients
                 .from_def(&w.entry.as_ref().unwrap().definition)
                 .expect("No ingredient by that name")
-                .clone()
+                .with_portion(portion.unwrap_or_default())
+                .with_preparation(preparation.unwrap_or_default())
         },
     )(input)
 }
 
-fn word_with_def(
+pub fn word_with_def(
     input: &[AnnotatedWord],
     def: WordFunction,
 ) -> IResult<&[AnnotatedWord], &AnnotatedWord> {
@@ -422,140 +523,12 @@ pub fn word_with_role(
     }
 }
 
-/// Matches a negated phrase to reverse the inner meaning, either "not A" or just "A".
-fn adv_p<'a>(
-    input: &'a [AnnotatedWord],
-    lang: &Personality,
-) -> IResult<&'a [AnnotatedWord], Parsed> {
-    let react = move |adv: &AnnotatedWord, (vp, vp_l): Parsed| {
-        let op = if thread_rng().gen_bool(lang.adverbs) {
-            match adv.definition() {
-                Some(WordFunction::Ever) => Box::new(ops::Persist(vp)) as Box<dyn Operation>,
-                Some(WordFunction::Negation) => vp.reverse(),
-                Some(WordFunction::Question) => vp.question(),
-                _ => todo!(),
-            }
-        } else {
-            vp
-        };
-        (
-            op,
-            Language {
-                adverbs: vp_l.adverbs + 1,
-                ..vp_l
-            },
-        )
-    };
-    let x = alt((
-        |i| {
-            // Pick a word order based on our language system.
-            // TODO Try to pull this out into a generic `ordered_pair` function?
-            if thread_rng().gen_bool(lang.adverb_side) {
-                map(
-                    pair(|i| word_with_role(i, WordRole::Adverb), |i| adv_p(i, lang)),
-                    |(adv, (vp, vp_l))| {
-                        react(
-                            adv,
-                            (
-                                vp,
-                                Language {
-                                    adverb_side: 1,
-                                    ..vp_l
-                                },
-                            ),
-                        )
-                    },
-                )(i)
-            } else {
-                map(
-                    pair(|i| adv_p(i, lang), |i| word_with_role(i, WordRole::Adverb)),
-                    |((vp, vp_l), adv)| {
-                        react(
-                            adv,
-                            (
-                                vp,
-                                Language {
-                                    adverb_side: -1,
-                                    ..vp_l
-                                },
-                            ),
-                        )
-                    },
-                )(i)
-            }
-        },
-        |i| pos_p(i, lang),
-    ))(input);
-    x
-}
-
-fn adposition<'a>(
-    input: &'a [AnnotatedWord],
-    lang: &Personality,
-) -> IResult<&'a [AnnotatedWord], ops::Relative> {
-    map(
-        pair(
-            |i| ingredient(i, lang),
-            |i| word_with_role(i, WordRole::Preposition),
-        ),
-        |(ingr, pos)| ops::Relative::from_def(pos.entry.as_ref().unwrap().function, ingr),
-    )(input)
-}
-
-fn number(input: &[AnnotatedWord]) -> IResult<&[AnnotatedWord], u32> {
-    map_res(
-        |i| word_with_def(i, WordFunction::Number),
-        |w| w.entry.as_ref().unwrap().definition.parse::<u32>(),
-    )(input)
-}
-
 pub type Parsed = (Box<dyn Operation>, Language);
 // struct Parsed {
 //     op: Box<dyn Operation>,
 //     lang: Language,
 // }
 
-/// Matches numbered phrases, either "do A, X times" or just "A".
-fn numbered_p<'a>(
-    input: &'a [AnnotatedWord],
-    lang: &Personality,
-) -> IResult<&'a [AnnotatedWord], Parsed> {
-    alt((
-        map(pair(number, |i| numbered_p(i, lang)), |(n, (vp, l))| {
-            (
-                if thread_rng().gen_bool(lang.numbers) {
-                    Box::new(ops::Repeat(n, vp)) as Box<dyn Operation>
-                } else {
-                    vp
-                },
-                Language {
-                    numbers: l.numbers + 1,
-                    ..l
-                },
-            )
-        }),
-        |i| adv_p(i, lang),
-    ))(input)
-}
-
-/// Matches prepositional phrases, either "A prep B" or just "A".
-fn pos_p<'a>(
-    input: &'a [AnnotatedWord],
-    lang: &Personality,
-) -> IResult<&'a [AnnotatedWord], Parsed> {
-    if thread_rng().gen_bool(lang.adposition) {
-        alt((
-            |i| adposition(i, lang).and_then(|(i, r)| clause_new(i, &r, lang)),
-            |i| clause_new(i, &ops::Relative::Top, lang),
-        ))(input)
-    } else {
-        // Skip over the adposition if we don't understand it.
-        preceded(opt(|i| adposition(i, lang)), |i| {
-            clause_new(i, &ops::Relative::Top, lang)
-        })(input)
-    }
-}
-
 fn greeting<'a>(input: &'a [AnnotatedWord]) -> IResult<&'a [AnnotatedWord], Parsed> {
     map(
         |i| word_with_def(i, WordFunction::Greeting),
@@ -579,78 +552,137 @@ fn affirmation<'a>(input: &'a [AnnotatedWord]) -> IResult<&'a [AnnotatedWord], P
     )(input)
 }
 
+/// A bare "no", refusing or answering whatever was said last.
+fn negation<'a>(input: &'a [AnnotatedWord]) -> IResult<&'a [AnnotatedWord], Parsed> {
+    map(
+        |i| word_with_def(i, WordFunction::Negation),
+        |_| {
+            (
+                Box::new(ops::NegateLast) as Box<dyn Operation>,
+                Language::default(),
+            )
+        },
+    )(input)
+}
+
 /// Top level sentence parser, either some general phrase or a special one like
 /// a greeting.
 fn sentence<'a>(
     input: &'a [AnnotatedWord],
     lang: &Personality,
 ) -> IResult<&'a [AnnotatedWord], Parsed> {
-    alt((|i| conjuncted_phrase(i, lang), affirmation, greeting))(input)
+    alt((
+        |i| change::parse_with(i, lang, change::CHANGES),
+        |i| payment(i, lang),
+        affirmation,
+        negation,
+        greeting,
+    ))(input)
 }
 
-/// VP -> (NP) V
-pub fn clause_new<'a>(
+/// A price to pay, spelled with the same number words as anything else,
+/// like "ko mo kahi" for seven.
+fn payment<'a>(
     input: &'a [AnnotatedWord],
-    pos: &ops::Relative,
     lang: &Personality,
 ) -> IResult<&'a [AnnotatedWord], Parsed> {
-    map_opt(
-        pair(
-            |i| ingredient(i, lang),
-            |i| word_with_role(i, WordRole::Verb),
-        ),
-        |(np, v)| match v.definition() {
-            Some(WordFunction::Desire) => Some((
-                Box::new(ops::Add(np, pos.clone())) as Box<dyn Operation>,
-                Language {
-                    adposition: if *pos == ops::Relative::Top { 0 } else { 1 },
-                    ..Default::default()
-                },
-            )),
-            Some(WordFunction::Have) => Some((
-                Box::new(ops::Ensure(np)) as Box<dyn Operation>,
-                Language::default(),
-            )),
-            _ => None, // _ => todo!("This verb hasn't been mapped to an operation yet."),
+    map(
+        |i| {
+            change::ordered_pair(i, lang, change::OBJECT_FIRST, change::number, |i| {
+                word_with_def(i, WordFunction::Pay)
+            })
         },
+        |(n, _, order)| (Box::new(ops::Pay(n)) as Box<dyn Operation>, order),
     )(input)
 }
 
-/// Matches "A and B" or just "A"
-/// TODO Move around the position of the conjunction.
-fn conjuncted_phrase<'a>(
+/// The second object some verbs take, joined on with "and" as in "bacon and
+/// cheese swap", or marked like an adposition as in "bacon cheese with
+/// replace". Speakers who haven't picked either up just say the bare noun.
+fn second_object<'a>(
     input: &'a [AnnotatedWord],
     lang: &Personality,
-) -> IResult<&'a [AnnotatedWord], Parsed> {
-    let inner = |i| numbered_p(i, lang);
+) -> IResult<&'a [AnnotatedWord], (Ingredient, Language)> {
     alt((
         map(
-            separated_pair(
-                inner,
+            preceded(
                 |i| word_with_def(i, WordFunction::And),
-                // Allow recursion on conjunctions for X and (X and X), etc.
-                |i| conjuncted_phrase(i, lang),
+                |i| ingredient(i, lang),
             ),
-            |((a, a_l), (b, b_l))| {
-                let op = if thread_rng().gen_bool(lang.conjunction) {
-                    Box::new(ops::Compound(a, b)) as Box<dyn Operation>
-                } else {
-                    b
-                };
-                (
-                    op,
-                    a_l + b_l
-                        + Language {
-                            conjunction: 1,
-                            ..Default::default()
-                        },
+            |np| (np, change::Conjunction.exercise()),
+        ),
+        map(
+            |i| {
+                change::ordered_pair(
+                    i,
+                    lang,
+                    change::POSTPOSITION,
+                    |i| ingredient(i, lang),
+                    |i| word_with_def(i, WordFunction::With),
                 )
             },
+            |(np, _, order)| (np, change::AddAfter.exercise() + order),
         ),
-        inner,
+        map(|i| ingredient(i, lang), |np| (np, Language::default())),
     ))(input)
 }
 
+/// VP -> (NP) V, or V (NP) for speakers who've drifted towards verb-first.
+/// Some verbs take a second object, like "swap bacon and cheese".
+pub fn clause_new<'a>(
+    input: &'a [AnnotatedWord],
+    pos: &ops::Relative,
+    lang: &Personality,
+) -> IResult<&'a [AnnotatedWord], Parsed> {
+    map_opt(
+        |i| {
+            let (i, ((np, second), v, order)) = change::ordered_pair(
+                i,
+                lang,
+                change::OBJECT_FIRST,
+                pair(|i| ingredient(i, lang), opt(|i| second_object(i, lang))),
+                |i| word_with_role(i, WordRole::Verb),
+            )?;
+            Ok(match second {
+                Some((np2, marked)) => (i, ((np, Some(np2)), v, order + marked)),
+                None => (i, ((np, None), v, order)),
+            })
+        },
+        |((np, np2), v, order)| match (v.definition(), np2) {
+            (Some(WordFunction::Desire), None) => Some((
+                Box::new(ops::Add(np, pos.clone())) as Box<dyn Operation>,
+                order,
+            )),
+            (Some(WordFunction::Have), None) => {
+                Some((Box::new(ops::Ensure(np)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Make), None) => {
+                Some((Box::new(ops::SetPortion(np)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Prepare), None) => {
+                Some((Box::new(ops::Prepare(np)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Give), None) => {
+                Some((Box::new(ops::Give(np)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Take), None) => {
+                Some((Box::new(ops::Take(np)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Move), None) => Some((
+                Box::new(ops::Move(np, pos.clone())) as Box<dyn Operation>,
+                order,
+            )),
+            (Some(WordFunction::Swap), Some(np2)) => {
+                Some((Box::new(ops::Swap(np, np2)) as Box<dyn Operation>, order))
+            }
+            (Some(WordFunction::Replace), Some(np2)) => {
+                Some((Box::new(ops::Replace(np, np2)) as Box<dyn Operation>, order))
+            }
+            _ => None, // _ => todo!("This verb hasn't been mapped to an operation yet."),
+        },
+    )(input)
+}
+
 /// For each word, a distribution of possible parts of speech.
 pub type Weights<T> = Vec<(T, u32)>;
 pub type POSCloud<'a> = HashMap<String, Weights<WordRole>>;
@@ -659,6 +691,8 @@ pub struct FullParse {
     pub operation: Box<dyn Operation>,
     pub lang: Language,
     pub lex: AnnotatedPhrase,
+    /// Whether they said "please" anywhere in there.
+    pub polite: bool,
 }
 
 pub fn prob_sentence_new(input: &[u8], lang: &Personality) -> Option<FullParse> {
@@ -666,11 +700,13 @@ pub fn prob_sentence_new(input: &[u8], lang: &Personality) -> Option<FullParse>
         // Generate possible annotations until we find a successful parse.
         for _ in 0..200 {
             let tagged = prob_annotate(&words, lang);
-            if let Ok((_, res)) = sentence(&tagged, lang) {
+            let (plain, polite) = without_politeness(&tagged);
+            if let Ok((_, res)) = sentence(&plain, lang) {
                 return Some(FullParse {
                     operation: res.0,
                     lang: res.1,
                     lex: tagged,
+                    polite,
                 });
             }
         }
@@ -709,3 +745,101 @@ pub fn prob_annotate(phrase: &Phrase, context: &Personality) -> AnnotatedPhrase
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn say(lang: &Personality, op: &dyn Operation) -> String {
+        op.encode(lang).iter().map(|w| w.word.to_string()).join(" ")
+    }
+
+    /// Every count spells out as a numeral that reads back as the same count,
+    /// including right around each "five".
+    #[test]
+    fn numerals_round_trip() {
+        let dict = Dictionary::new();
+        for n in (1..=130).chain(vec![624, 625, 626]) {
+            let numeral = dict.numeral(n);
+            let said = numeral.iter().map(|w| w.word.to_string()).join(" ");
+            assert_eq!(change::number(&numeral), Ok((&[][..], n)), "{}", said);
+        }
+    }
+
+    /// Repeating something a few times over is heard as such, however many
+    /// words the count takes.
+    #[test]
+    fn repeats_round_trip() {
+        let lang = Personality::fluent(1.0);
+        let avocado = lang.dictionary.ingredients.from_def("avocado").unwrap();
+        for &n in &[1, 2, 5, 6, 25, 26] {
+            let op = ops::Repeat(n, Box::new(ops::Add(avocado.clone(), ops::Relative::Top)));
+            let said = say(&lang, &op);
+            let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+            assert_eq!(
+                format!("{:?}", parsed.operation),
+                format!("{:?}", op),
+                "{}",
+                said
+            );
+        }
+    }
+
+    /// Swap joins its two ingredients with "and", and Replace marks the new
+    /// one like an adposition, either way around. Both are heard back along
+    /// with the skills they used.
+    #[test]
+    fn second_objects_round_trip() {
+        for &(order, heard) in &[(1.0, 1), (0.0, -1)] {
+            let lang = Personality::fluent(order);
+            let ingredient = |name| lang.dictionary.ingredients.from_def(name).unwrap().clone();
+            let (a, b) = (ingredient("avocado"), ingredient("bacon"));
+
+            let swap = ops::Swap(a.clone(), b.clone());
+            let said = say(&lang, &swap);
+            let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+            assert_eq!(
+                format!("{:?}", parsed.operation),
+                format!("{:?}", swap),
+                "{}",
+                said
+            );
+            assert_eq!(parsed.lang.get(change::CONJUNCTION), 1, "{}", said);
+
+            let replace = ops::Replace(a.clone(), b.clone());
+            let said = say(&lang, &replace);
+            let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+            assert_eq!(
+                format!("{:?}", parsed.operation),
+                format!("{:?}", replace),
+                "{}",
+                said
+            );
+            assert_eq!(parsed.lang.get(change::ADPOSITION), 1, "{}", said);
+            assert_eq!(parsed.lang.get(change::POSTPOSITION), heard, "{}", said);
+        }
+    }
+
+    /// Speakers who haven't picked up either marker can still name both
+    /// ingredients bare.
+    #[test]
+    fn bare_second_object() {
+        let lang = Personality::fluent(1.0);
+        let word = |name| {
+            lang.dictionary
+                .ingredients
+                .to_annotated_word(lang.dictionary.ingredients.from_def(name).unwrap())
+                .word
+                .to_string()
+        };
+        let swap = lang.dictionary.word_for_def(WordFunction::Swap).0;
+        let said = format!("{} {} {}", word("avocado"), word("bacon"), swap);
+        let parsed = sentence_new(said.as_bytes(), &lang).expect(&said);
+        assert!(
+            format!("{:?}", parsed.operation).starts_with("Swap("),
+            "{}",
+            said
+        );
+        assert_eq!(parsed.lang.get(change::CONJUNCTION), 0, "{}", said);
+    }
+}
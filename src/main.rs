@@ -1,5 +1,6 @@
 mod audio;
 mod behavior;
+mod change;
 mod client;
 mod comm;
 mod display;
@@ -7,15 +8,25 @@ mod grammar;
 mod sandwich;
 mod sawtooth;
 mod state;
+mod timeline;
 
 use anyhow;
 use client::Client;
 use rand::prelude::*;
 use std::thread;
 use std::time::Duration;
+use timeline::Timeline;
 
 #[async_std::main]
 async fn main() -> anyhow::Result<()> {
+    // Just show what the timeline has in store, without running anything.
+    if std::env::args().any(|a| a == "--dry-run") {
+        match Timeline::load()? {
+            Some(timeline) => timeline.dry_run(),
+            None => println!("There's no timeline.yml to run."),
+        }
+        return Ok(());
+    }
     let mut c = Client::new();
     c.add_behavior(behavior::Forgetful::new(0.3));
     if comm::is_dispatch_host() {
@@ -8,12 +8,113 @@ use serde::{Deserialize, Serialize};
 use serde_yaml;
 use std::fmt::{self, Display};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash)]
+/// How much of an ingredient goes into one layer.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Portion {
+    Light,
+    #[default]
+    Normal,
+    Extra,
+    Double,
+}
+impl Portion {
+    pub const ALL: &'static [Portion] = &[
+        Portion::Light,
+        Portion::Normal,
+        Portion::Extra,
+        Portion::Double,
+    ];
+
+    /// How many normal portions' worth of stock this takes.
+    pub fn amount(self) -> f64 {
+        match self {
+            Portion::Light => 0.5,
+            Portion::Normal => 1.0,
+            Portion::Extra => 1.5,
+            Portion::Double => 2.0,
+        }
+    }
+
+    /// The definition of the word for this portion in the dictionary.
+    pub fn definition(self) -> &'static str {
+        match self {
+            Portion::Light => "light",
+            Portion::Normal => "normal",
+            Portion::Extra => "extra",
+            Portion::Double => "double",
+        }
+    }
+
+    pub fn from_def(definition: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|p| p.definition() == definition)
+    }
+}
+
+/// How an ingredient was cooked, or not, before going on the sandwich.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Preparation {
+    #[default]
+    Plain,
+    Toasted,
+    Grilled,
+    Melted,
+    Cold,
+}
+impl Preparation {
+    pub const ALL: &'static [Preparation] = &[
+        Preparation::Plain,
+        Preparation::Toasted,
+        Preparation::Grilled,
+        Preparation::Melted,
+        Preparation::Cold,
+    ];
+
+    /// The definition of the word for this preparation in the dictionary.
+    pub fn definition(self) -> &'static str {
+        match self {
+            Preparation::Plain => "plain",
+            Preparation::Toasted => "toasted",
+            Preparation::Grilled => "grilled",
+            Preparation::Melted => "melted",
+            Preparation::Cold => "cold",
+        }
+    }
+
+    pub fn from_def(definition: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|p| p.definition() == definition)
+    }
+}
+
+/// Ingredients are the same no matter how much of them there is or how
+/// they're cooked, so equality only looks at the name.
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Ingredient {
     pub name: String,
     morpheme: String,
     children: Option<Vec<Ingredient>>,
+    #[serde(default)]
+    pub portion: Portion,
+    #[serde(default)]
+    pub preparation: Preparation,
+}
+impl PartialEq for Ingredient {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+impl Eq for Ingredient {}
+impl Hash for Ingredient {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
+    }
 }
 impl Ingredient {
     pub fn all() -> Self {
@@ -25,6 +126,27 @@ impl Ingredient {
         &self.name
     }
 
+    /// The same ingredient in a different amount.
+    pub fn with_portion(&self, portion: Portion) -> Self {
+        Self {
+            portion,
+            ..self.clone()
+        }
+    }
+
+    /// The same ingredient cooked a different way.
+    pub fn with_preparation(&self, preparation: Preparation) -> Self {
+        Self {
+            preparation,
+            ..self.clone()
+        }
+    }
+
+    /// Whether this layer is portioned and cooked just like the other one.
+    pub fn same_style(&self, other: &Ingredient) -> bool {
+        self.portion == other.portion && self.preparation == other.preparation
+    }
+
     pub fn includes(&self, other: &Ingredient) -> bool {
         self == other
             || self
@@ -47,6 +169,28 @@ impl Ingredient {
         }
     }
 
+    /// Other bottom-level ingredients in the same category as the given one,
+    /// like other cheeses for cheddar. Bases have no neighbors.
+    pub fn neighbors(&self, ingredient: &Ingredient) -> Vec<&Ingredient> {
+        let children = match &self.children {
+            Some(children) => children,
+            None => return Vec::new(),
+        };
+        if children.contains(ingredient) {
+            children
+                .iter()
+                .filter(|x| *x != ingredient && x.children.is_none())
+                .collect()
+        } else {
+            children
+                .iter()
+                .filter(|x| x.name != "base")
+                .map(|x| x.neighbors(ingredient))
+                .find(|x| !x.is_empty())
+                .unwrap_or_default()
+        }
+    }
+
     pub fn random_base(&self) -> (&Ingredient, &Ingredient) {
         self.children
             .as_ref()
@@ -146,6 +290,12 @@ impl Display for Sandwich {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "[")?;
         for ingr in &self.ingredients {
+            if ingr.portion != Portion::Normal {
+                write!(f, "{} ", ingr.portion.definition())?;
+            }
+            if ingr.preparation != Preparation::Plain {
+                write!(f, "{} ", ingr.preparation.definition())?;
+            }
             write!(f, "{}, ", ingr.name)?;
         }
         write!(f, "]")
@@ -191,6 +341,28 @@ impl Sandwich {
             background_color: BG_COLORS.choose(&mut rng).unwrap().to_string(),
         }
     }
+    /// Whether both sandwiches have the same layers, portioned and cooked the
+    /// same way. Plain equality on ingredients only looks at their names.
+    pub fn same_layers(&self, other: &Sandwich) -> bool {
+        self.ingredients.len() == other.ingredients.len()
+            && self
+                .ingredients
+                .iter()
+                .zip(&other.ingredients)
+                .all(|(a, b)| a == b && a.same_style(b))
+    }
+    /// Whether both sandwiches have the same ingredients the same number of
+    /// times, no matter how they're stacked.
+    pub fn same_ingredients(&self, other: &Sandwich) -> bool {
+        let names = |s: &Sandwich| {
+            s.ingredients
+                .iter()
+                .map(|x| x.name.clone())
+                .sorted()
+                .collect_vec()
+        };
+        names(self) == names(other)
+    }
     pub fn to_words(&self, dictionary: &Dictionary) -> Vec<String> {
         self.ingredients
             .iter()
@@ -102,9 +102,11 @@ impl State for MakingSandwich {
         let (word, sammich) = match verb {
             WordFunction::Greeting => (WordFunction::Greeting, Some(self.sandwich.clone())),
             WordFunction::Desire => {
-                encoder.decode(input, &mut self.sandwich, lang);
-                // TODO Say "no" or more if decode fails.
-                (WordFunction::Affirmation, None)
+                if encoder.decode(input, &mut self.sandwich, lang) {
+                    (WordFunction::Affirmation, None)
+                } else {
+                    (WordFunction::Negation, None)
+                }
             }
             _ => (WordFunction::Negation, None),
         };
@@ -116,6 +118,7 @@ impl State for MakingSandwich {
                 ingredients: Some(self.sandwich.ingredients.clone()),
                 subtitles: Some(entry.definition.clone()),
                 background: None,
+                mood: None,
             })
             .unwrap();
 
@@ -0,0 +1,227 @@
+//! A day at the installation, run by dispatch so that the room takes care of
+//! itself: waking up, lunch rushes, deliveries, and going dark at night.
+//! Each cue happens at a local time of day, every day, as laid out in
+//! `timeline.yml`.
+
+use crate::{
+    behavior::{DispatchMessage, EVENTS},
+    comm,
+    sandwich::Ingredient,
+};
+use async_std::{net::TcpStream, task};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::ErrorKind,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Timeline {
+    /// Hours ahead of UTC where the installation is, which can be negative.
+    #[serde(default)]
+    pub utc_offset: i64,
+    pub cues: Vec<Cue>,
+}
+
+/// Something to do at a set time each day.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Cue {
+    /// Local time of day, like "13:30".
+    pub at: String,
+    #[serde(rename = "do")]
+    pub action: Action,
+}
+impl Cue {
+    /// Seconds past midnight this cue happens.
+    fn time_of_day(&self) -> anyhow::Result<u64> {
+        let (hours, minutes) = self
+            .at
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Cue time {} should look like 13:30", self.at))?;
+        let (hours, minutes): (u64, u64) = (hours.trim().parse()?, minutes.trim().parse()?);
+        anyhow::ensure!(
+            hours < 24 && minutes < 60,
+            "Cue time {} isn't a time of day",
+            self.at
+        );
+        Ok(hours * 3600 + minutes * 60)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Wake every machine up to start taking orders.
+    Open,
+    /// Put every machine to sleep with a blank screen.
+    Sleep,
+    /// A fresh delivery for every machine.
+    Restock,
+    /// Nudge every machine towards liking an ingredient.
+    Prefer(String),
+    /// Set off an event, rippling out from one machine or hitting the whole room.
+    Event {
+        name: String,
+        #[serde(default)]
+        from: Option<String>,
+    },
+}
+impl Action {
+    /// What dispatch sends to which machine to carry this out.
+    pub fn messages(&self) -> Vec<(&'static str, DispatchMessage)> {
+        let to_all = |msg: DispatchMessage| -> Vec<_> {
+            comm::HOSTS.iter().map(|h| (*h, msg.clone())).collect()
+        };
+        match self {
+            Action::Open => to_all(DispatchMessage::Wake),
+            Action::Sleep => to_all(DispatchMessage::Sleep),
+            Action::Restock => to_all(DispatchMessage::Restock),
+            Action::Prefer(name) => to_all(DispatchMessage::Prefer(name.clone())),
+            Action::Event { name, from } => comm::event_wave(name, from.as_deref())
+                .into_iter()
+                .map(|(h, notice)| (h, DispatchMessage::Event(notice)))
+                .collect(),
+        }
+    }
+    /// Whether this opens or closes the room for the day.
+    fn sets_hours(&self) -> bool {
+        matches!(self, Action::Open | Action::Sleep)
+    }
+}
+
+impl Timeline {
+    /// Reads `timeline.yml`, if there is one.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let file = match File::open("timeline.yml") {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let timeline: Self = serde_yaml::from_reader(file)?;
+        // Catch mistakes up front rather than in the middle of the day.
+        let ingredients = Ingredient::all();
+        for cue in &timeline.cues {
+            cue.time_of_day()?;
+            match &cue.action {
+                Action::Event { name, from } => {
+                    anyhow::ensure!(EVENTS.contains_key(name), "There's no {} event", name);
+                    if let Some(host) = from {
+                        anyhow::ensure!(
+                            comm::HOSTS.contains(&host.as_str()),
+                            "There's no {} machine",
+                            host
+                        );
+                    }
+                }
+                Action::Prefer(name) => anyhow::ensure!(
+                    ingredients.from_def(name).is_some(),
+                    "There's no {} ingredient",
+                    name
+                ),
+                _ => {}
+            }
+        }
+        Ok(Some(timeline))
+    }
+
+    /// Seconds past local midnight right now.
+    fn now(&self) -> u64 {
+        let utc = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        (utc + self.utc_offset * 3600).rem_euclid(DAY as i64) as u64
+    }
+
+    /// Seconds from `now` until the cue comes around again, a full day if
+    /// it's happening right now.
+    fn until(&self, cue: &Cue, now: u64) -> u64 {
+        match (cue.time_of_day().unwrap() + DAY - now) % DAY {
+            0 => DAY,
+            wait => wait,
+        }
+    }
+
+    /// The latest opening or closing cue before now, to put the room in the
+    /// state it should already be in.
+    fn current_hours(&self, now: u64) -> Option<&Cue> {
+        self.cues
+            .iter()
+            .filter(|c| c.action.sets_hours())
+            .max_by_key(|c| self.until(c, now))
+    }
+
+    /// Every cue from now through the next day, soonest first, with how long
+    /// until each.
+    fn upcoming(&self, now: u64) -> Vec<(u64, &Cue)> {
+        let mut cues: Vec<_> = self.cues.iter().map(|c| (self.until(c, now), c)).collect();
+        cues.sort_by_key(|(wait, _)| *wait);
+        cues
+    }
+
+    /// Runs the show forever, one day after another.
+    pub async fn run(
+        &self,
+        connections: &mut HashMap<&'static str, TcpStream>,
+    ) -> anyhow::Result<()> {
+        if let Some(cue) = self.current_hours(self.now()) {
+            println!("Starting off with {:?}", cue.action);
+            Self::send(&cue.action, connections).await?;
+        }
+        loop {
+            let upcoming = self.upcoming(self.now());
+            let wait = match upcoming.first() {
+                Some((wait, _)) => *wait,
+                None => return Ok(()),
+            };
+            task::sleep(Duration::from_secs(wait)).await;
+            // Anything scheduled for the same time goes out together.
+            for (_, cue) in upcoming.iter().take_while(|(w, _)| *w == wait) {
+                println!("{} {:?}", cue.at, cue.action);
+                Self::send(&cue.action, connections).await?;
+            }
+        }
+    }
+
+    async fn send(
+        action: &Action,
+        connections: &mut HashMap<&'static str, TcpStream>,
+    ) -> anyhow::Result<()> {
+        for (host, msg) in action.messages() {
+            if let Some(stream) = connections.get_mut(host) {
+                msg.send(stream).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints out what the next day would look like, without sending anything.
+    pub fn dry_run(&self) {
+        let now = self.now();
+        println!("It's {} now.", clock(now));
+        if let Some(cue) = self.current_hours(now) {
+            println!("Right away, as of {}: {:?}", cue.at, cue.action);
+        }
+        for (wait, cue) in self.upcoming(now) {
+            println!(
+                "{} (in {}h{:02}m): {:?}",
+                cue.at,
+                wait / 3600,
+                wait % 3600 / 60,
+                cue.action
+            );
+            for (host, msg) in cue.action.messages() {
+                println!("    {} <- {:?}", host, msg);
+            }
+        }
+    }
+}
+
+/// Spells out seconds past midnight as a time of day.
+fn clock(seconds: u64) -> String {
+    format!("{:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}